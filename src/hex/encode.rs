@@ -0,0 +1,210 @@
+//! Text encodings for moving binary through text-only channels.
+
+use super::{DumpConfig, Dumper};
+use crate::output::buffer::LineBuffer;
+use clap::ValueEnum;
+use std::io::{self, Write};
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// First character of the ascii85 alphabet; digit `d` is `ASCII85_BASE + d`.
+pub const ASCII85_BASE: u8 = b'!';
+
+/// Text encoding of binary data.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Encoding {
+    Base64,
+    Base32,
+    Ascii85,
+}
+
+impl Encoding {
+    /// Bits carried by one character of a base64/base32 encoding.
+    #[inline(always)]
+    pub fn bits_per_char(self) -> u32 {
+        match self {
+            Encoding::Base64 => 6,
+            Encoding::Base32 => 5,
+            Encoding::Ascii85 => unreachable!("ascii85 is not a bit-aligned encoding"),
+        }
+    }
+
+    /// Characters per full group of the encoding.
+    #[inline(always)]
+    fn group_chars(self) -> u64 {
+        match self {
+            Encoding::Base64 => 4,
+            Encoding::Base32 => 8,
+            Encoding::Ascii85 => 5,
+        }
+    }
+
+    /// Value of a base64/base32 digit, or `None` if `c` is not in the alphabet.
+    #[inline(always)]
+    pub fn digit_value(self, c: u8) -> Option<u8> {
+        match self {
+            Encoding::Base64 => match c {
+                b'A'..=b'Z' => Some(c - b'A'),
+                b'a'..=b'z' => Some(c - b'a' + 26),
+                b'0'..=b'9' => Some(c - b'0' + 52),
+                b'+' => Some(62),
+                b'/' => Some(63),
+                _ => None,
+            },
+            Encoding::Base32 => match c {
+                b'A'..=b'Z' => Some(c - b'A'),
+                b'a'..=b'z' => Some(c - b'a'),
+                b'2'..=b'7' => Some(c - b'2' + 26),
+                _ => None,
+            },
+            Encoding::Ascii85 => match c {
+                b'!'..=b'u' => Some(c - ASCII85_BASE),
+                _ => None,
+            },
+        }
+    }
+
+    #[inline(always)]
+    fn digit(self, value: u8) -> u8 {
+        match self {
+            Encoding::Base64 => BASE64_ALPHABET[value as usize],
+            Encoding::Base32 => BASE32_ALPHABET[value as usize],
+            Encoding::Ascii85 => ASCII85_BASE + value,
+        }
+    }
+}
+
+/// Encoding dumper: streams base64, base32 or ascii85 text wrapped at
+/// `bytes_per_line` characters. Partial groups are carried across chunks.
+pub struct EncodeDumper {
+    config: DumpConfig,
+    encoding: Encoding,
+    line_buf: LineBuffer,
+    /// Characters on the current output line.
+    column: usize,
+    /// Characters emitted so far, for padding the final group.
+    chars: u64,
+    /// Pending bits not yet emitted (base64/base32).
+    acc: u32,
+    bits: u32,
+    /// Pending bytes of an incomplete ascii85 group.
+    group: [u8; 4],
+    group_len: usize,
+}
+
+impl EncodeDumper {
+    pub fn new(config: DumpConfig, encoding: Encoding) -> Self {
+        Self {
+            config,
+            encoding,
+            line_buf: LineBuffer::new(),
+            column: 0,
+            chars: 0,
+            acc: 0,
+            bits: 0,
+            group: [0; 4],
+            group_len: 0,
+        }
+    }
+
+    #[inline(always)]
+    fn emit<W: Write>(&mut self, c: u8, writer: &mut W) -> io::Result<()> {
+        self.line_buf.push(c);
+        self.column += 1;
+        self.chars += 1;
+
+        if self.column >= self.config.bytes_per_line {
+            self.line_buf.push(b'\n');
+            self.column = 0;
+            writer.write_all(self.line_buf.as_slice())?;
+            self.line_buf.reset();
+        } else if self.line_buf.len() >= 1000 {
+            // Very long lines are written in pieces
+            writer.write_all(self.line_buf.as_slice())?;
+            self.line_buf.reset();
+        }
+        Ok(())
+    }
+
+    /// Emit the five digits of an ascii85 group, or the first `n` of them.
+    #[inline(always)]
+    fn emit_ascii85<W: Write>(&mut self, n: usize, writer: &mut W) -> io::Result<()> {
+        let mut value = u32::from_be_bytes(self.group);
+        if n == 5 && value == 0 {
+            return self.emit(b'z', writer);
+        }
+
+        let mut digits = [0u8; 5];
+        for digit in digits.iter_mut().rev() {
+            *digit = (value % 85) as u8;
+            value /= 85;
+        }
+        for &digit in &digits[..n] {
+            self.emit(self.encoding.digit(digit), writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl Dumper for EncodeDumper {
+    #[inline]
+    fn process<W: Write>(&mut self, data: &[u8], writer: &mut W) -> io::Result<()> {
+        if self.encoding == Encoding::Ascii85 {
+            for &byte in data {
+                self.group[self.group_len] = byte;
+                self.group_len += 1;
+                if self.group_len == 4 {
+                    self.emit_ascii85(5, writer)?;
+                    self.group_len = 0;
+                }
+            }
+            return Ok(());
+        }
+
+        let width = self.encoding.bits_per_char();
+        let mask = (1u32 << width) - 1;
+        for &byte in data {
+            self.acc = self.acc << 8 | byte as u32;
+            self.bits += 8;
+            while self.bits >= width {
+                self.bits -= width;
+                let value = (self.acc >> self.bits & mask) as u8;
+                self.emit(self.encoding.digit(value), writer)?;
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn finish<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        if self.encoding == Encoding::Ascii85 {
+            // A partial group of n bytes is zero-padded and written as n + 1 digits
+            if self.group_len > 0 {
+                let n = self.group_len;
+                self.group[n..].fill(0);
+                self.emit_ascii85(n + 1, writer)?;
+                self.group_len = 0;
+            }
+        } else {
+            let width = self.encoding.bits_per_char();
+            if self.bits > 0 {
+                let value = (self.acc << (width - self.bits) & ((1 << width) - 1)) as u8;
+                self.bits = 0;
+                self.emit(self.encoding.digit(value), writer)?;
+            }
+            while !self.chars.is_multiple_of(self.encoding.group_chars()) {
+                self.emit(b'=', writer)?;
+            }
+        }
+
+        if self.column > 0 {
+            self.line_buf.push(b'\n');
+            self.column = 0;
+        }
+        writer.write_all(self.line_buf.as_slice())?;
+        self.line_buf.reset();
+        Ok(())
+    }
+}
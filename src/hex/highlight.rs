@@ -0,0 +1,369 @@
+//! Highlight rules: styles for chosen byte values, offset ranges and byte
+//! patterns, drawn over the class colors.
+
+use super::Dumper;
+use crate::input::parse_size;
+use crate::output::theme::{entries, ColorDepth, Style};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Most rules one dump can use; marks are bytes, 0 means none and one
+/// is kept for search matches.
+const MAX_RULES: usize = 254;
+
+/// What a highlight rule applies to.
+#[derive(Clone)]
+enum Target {
+    /// Byte values `first..=last`.
+    Values(u8, u8),
+    /// Printed offsets `start..end`.
+    Range(u64, u64),
+    /// Every occurrence of a byte sequence.
+    Pattern(Vec<u8>),
+}
+
+/// One `--highlight`, `--highlight-range` or `--highlight-pattern` rule.
+#[derive(Clone)]
+pub struct Rule {
+    target: Target,
+    style: Style,
+}
+
+/// Split `SPEC=STYLE`, parsing the style.
+fn split_rule(s: &str) -> Result<(&str, Style), String> {
+    let (spec, style) = s
+        .rsplit_once('=')
+        .ok_or_else(|| format!("expected SPEC=STYLE: {}", s))?;
+    Ok((spec.trim(), Style::parse(style.trim())?))
+}
+
+fn parse_value(s: &str) -> Result<u8, String> {
+    let value = parse_size(s)?;
+    u8::try_from(value).map_err(|_| format!("not a byte value: {}", s))
+}
+
+/// Parse `VALUE=STYLE` or `FIRST-LAST=STYLE`, such as `0xff=red`.
+pub fn parse_value_rule(s: &str) -> Result<Rule, String> {
+    let (spec, style) = split_rule(s)?;
+    let (first, last) = match spec.split_once('-') {
+        Some((first, last)) => (parse_value(first)?, parse_value(last)?),
+        None => (parse_value(spec)?, parse_value(spec)?),
+    };
+    if first > last {
+        return Err(format!("empty value range: {}", spec));
+    }
+    Ok(Rule {
+        target: Target::Values(first, last),
+        style,
+    })
+}
+
+/// Parse `START..END=STYLE`, such as `0x100..0x140=blue`. The end is exclusive.
+pub fn parse_range_rule(s: &str) -> Result<Rule, String> {
+    let (spec, style) = split_rule(s)?;
+    let (start, end) = spec
+        .split_once("..")
+        .ok_or_else(|| format!("expected START..END: {}", spec))?;
+    let (start, end) = (parse_size(start)?, parse_size(end)?);
+    if start >= end {
+        return Err(format!("empty offset range: {}", spec));
+    }
+    Ok(Rule {
+        target: Target::Range(start, end),
+        style,
+    })
+}
+
+/// Parse `HEX=STYLE` with optional spaces between the bytes, such as
+/// `de ad be ef=magenta`, or a quoted ASCII string like `"PK"=yellow`.
+pub fn parse_pattern_rule(s: &str) -> Result<Rule, String> {
+    let (spec, style) = split_rule(s)?;
+    let pattern = match spec
+        .strip_prefix('"')
+        .and_then(|text| text.strip_suffix('"'))
+    {
+        Some(text) => text.as_bytes().to_vec(),
+        None => {
+            let digits: Vec<u8> = spec.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+            if !digits.len().is_multiple_of(2) {
+                return Err(format!("odd number of hex digits: {}", spec));
+            }
+            digits
+                .chunks(2)
+                .map(|pair| {
+                    std::str::from_utf8(pair)
+                        .ok()
+                        .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                        .ok_or_else(|| format!("invalid hex pattern: {}", spec))
+                })
+                .collect::<Result<_, _>>()?
+        }
+    };
+    if pattern.is_empty() {
+        return Err("empty pattern".to_string());
+    }
+    Ok(Rule {
+        target: Target::Pattern(pattern),
+        style,
+    })
+}
+
+/// Load a rules file: `value SPEC = STYLE`, `range SPEC = STYLE` and
+/// `pattern SPEC = STYLE` lines, with `#` comments.
+pub fn load_rules(path: &Path) -> io::Result<Vec<Rule>> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+
+    let mut rules = Vec::new();
+    for (line, key, style) in entries(&text, path)? {
+        let (kind, spec) = key.split_once(char::is_whitespace).unwrap_or((key, ""));
+        let rule = format!("{}={}", spec.trim(), style);
+        let parsed = match kind {
+            "value" => parse_value_rule(&rule),
+            "range" => parse_range_rule(&rule),
+            "pattern" => parse_pattern_rule(&rule),
+            _ => Err(format!(
+                "unknown rule: {} (expected value, range or pattern)",
+                kind
+            )),
+        };
+        let rule = parsed.map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}:{}: {}", path.display(), line, e),
+            )
+        })?;
+        rules.push(rule);
+    }
+    Ok(rules)
+}
+
+/// Highlight rules ready to match, with their escape codes. Each rule is
+/// identified by a mark, its index plus one. Patterns take precedence
+/// over ranges and ranges over values; among rules of one kind the later
+/// one wins.
+pub struct Highlights {
+    /// Mark of each byte value, 0 for none.
+    values: [u8; 256],
+    ranges: Vec<(u64, u64, u8)>,
+    patterns: Vec<(Vec<u8>, u8)>,
+    escapes: Vec<Vec<u8>>,
+    /// Mark of search matches, if they are highlighted.
+    matched: Option<u8>,
+}
+
+impl Highlights {
+    pub fn new(rules: &[Rule], depth: ColorDepth) -> io::Result<Highlights> {
+        if rules.len() > MAX_RULES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "too many highlight rules: {} (at most {})",
+                    rules.len(),
+                    MAX_RULES
+                ),
+            ));
+        }
+
+        let mut highlights = Highlights {
+            values: [0u8; 256],
+            ranges: Vec::new(),
+            patterns: Vec::new(),
+            escapes: Vec::with_capacity(rules.len()),
+            matched: None,
+        };
+        for (i, rule) in rules.iter().enumerate() {
+            let mark = i as u8 + 1;
+            match &rule.target {
+                Target::Values(first, last) => {
+                    highlights.values[*first as usize..=*last as usize].fill(mark);
+                }
+                Target::Range(start, end) => highlights.ranges.push((*start, *end, mark)),
+                Target::Pattern(pattern) => highlights.patterns.push((pattern.clone(), mark)),
+            }
+            highlights.escapes.push(rule.style.escape(depth));
+        }
+        // The later rule is tried first
+        highlights.ranges.reverse();
+        highlights.patterns.reverse();
+        Ok(highlights)
+    }
+
+    /// Add a mark for search matches, drawn with `escape`.
+    pub fn with_match(mut self, escape: Vec<u8>) -> Highlights {
+        self.escapes.push(escape);
+        self.matched = Some(self.escapes.len() as u8);
+        self
+    }
+
+    /// Mark of search matches, if `with_match` added one.
+    pub fn match_mark(&self) -> Option<u8> {
+        self.matched
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.escapes.is_empty()
+    }
+
+    /// Escape code of the rule with this nonzero mark.
+    #[inline(always)]
+    pub fn escape(&self, mark: u8) -> &[u8] {
+        &self.escapes[mark as usize - 1]
+    }
+
+    /// Bytes a pattern match may still need after a chunk ends.
+    fn lookahead(&self) -> usize {
+        self.patterns
+            .iter()
+            .map(|(pattern, _)| pattern.len() - 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Mark the unmarked bytes of every complete pattern match in `data`.
+    fn mark_patterns(&self, data: &[u8], marks: &mut [u8]) {
+        for (pattern, mark) in &self.patterns {
+            let Some(last_start) = data.len().checked_sub(pattern.len()) else {
+                continue;
+            };
+            for start in 0..=last_start {
+                if data[start] == pattern[0] && data[start..start + pattern.len()] == pattern[..] {
+                    for m in &mut marks[start..start + pattern.len()] {
+                        if *m == 0 {
+                            *m = *mark;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Mark the still unmarked bytes of `data`, printed from `offset`, by
+    /// range and value.
+    fn mark_bytes(&self, data: &[u8], offset: u64, marks: &mut [u8]) {
+        for (i, (m, &byte)) in marks.iter_mut().zip(data).enumerate() {
+            if *m != 0 {
+                continue;
+            }
+            let position = offset.wrapping_add(i as u64);
+            *m = self
+                .ranges
+                .iter()
+                .find(|(start, end, _)| (*start..*end).contains(&position))
+                .map_or(self.values[byte as usize], |&(_, _, mark)| mark);
+        }
+    }
+}
+
+/// Wrapper that marks the bytes matching highlight rules before the
+/// wrapped dumper sees them. The last bytes of each chunk are held back
+/// until the next one arrives, so patterns are found across chunks.
+pub struct HighlightDumper<D> {
+    inner: D,
+    highlights: &'static Highlights,
+    /// Whether there are rules and escape codes may be written.
+    active: bool,
+    /// Printed offset of the first held byte.
+    offset: u64,
+    /// Bytes not yet passed on, followed by the chunk being marked.
+    held: Vec<u8>,
+    /// Marks `held` arrived with, which win over every rule.
+    given: Vec<u8>,
+    /// Marks of `held`: the given ones, then pattern marks.
+    marks: Vec<u8>,
+}
+
+impl<D: Dumper> HighlightDumper<D> {
+    pub fn new(config: super::DumpConfig, inner: D) -> Self {
+        Self {
+            inner,
+            highlights: config.highlights,
+            active: config.ansi && !config.highlights.is_empty(),
+            offset: config.start_offset,
+            held: Vec::new(),
+            given: Vec::new(),
+            marks: Vec::new(),
+        }
+    }
+
+    /// Pass on the first `len` held bytes.
+    fn pass<W: Write>(&mut self, len: usize, writer: &mut W) -> io::Result<()> {
+        if len == 0 {
+            return Ok(());
+        }
+        self.highlights
+            .mark_bytes(&self.held[..len], self.offset, &mut self.marks[..len]);
+        self.inner
+            .process_marked(&self.held[..len], &self.marks[..len], writer)?;
+        self.held.drain(..len);
+        self.given.drain(..len);
+        self.marks.drain(..len);
+        self.offset = self.offset.wrapping_add(len as u64);
+        Ok(())
+    }
+
+    /// Mark patterns in the held bytes and pass on those no match can
+    /// still reach into. Bytes held back are marked afresh, so a later
+    /// rule matching into the new chunk still wins over an earlier one.
+    fn mark<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.marks.clear();
+        self.marks.extend_from_slice(&self.given);
+        self.highlights.mark_patterns(&self.held, &mut self.marks);
+        let ready = self.held.len().saturating_sub(self.highlights.lookahead());
+        self.pass(ready, writer)
+    }
+
+    /// Pass on every held byte; no pattern continues past a gap.
+    fn flush<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.pass(self.held.len(), writer)
+    }
+}
+
+impl<D: Dumper> Dumper for HighlightDumper<D> {
+    fn process<W: Write>(&mut self, data: &[u8], writer: &mut W) -> io::Result<()> {
+        if !self.active {
+            return self.inner.process(data, writer);
+        }
+        self.held.extend_from_slice(data);
+        self.given.resize(self.held.len(), 0);
+        self.mark(writer)
+    }
+
+    /// Bytes that arrive marked keep their marks over any rule.
+    fn process_marked<W: Write>(
+        &mut self,
+        data: &[u8],
+        marks: &[u8],
+        writer: &mut W,
+    ) -> io::Result<()> {
+        if !self.active {
+            return self.inner.process(data, writer);
+        }
+        self.held.extend_from_slice(data);
+        self.given.extend_from_slice(marks);
+        self.mark(writer)
+    }
+
+    fn finish<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.flush(writer)?;
+        self.inner.finish(writer)
+    }
+
+    fn hole<W: Write>(&mut self, len: u64, writer: &mut W) -> io::Result<()> {
+        self.flush(writer)?;
+        self.offset = self.offset.wrapping_add(len);
+        self.inner.hole(len, writer)
+    }
+
+    fn unreadable<W: Write>(&mut self, len: u64, writer: &mut W) -> io::Result<()> {
+        self.flush(writer)?;
+        self.offset = self.offset.wrapping_add(len);
+        self.inner.unreadable(len, writer)
+    }
+
+    fn flush_line<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.flush(writer)?;
+        self.inner.flush_line(writer)
+    }
+}
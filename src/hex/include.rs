@@ -0,0 +1,238 @@
+//! Source-code include mode: emit the input as an array or string literal.
+
+use super::{DumpConfig, Dumper, HEX_TABLE};
+use crate::output::buffer::LineBuffer;
+use clap::ValueEnum;
+use std::io::{self, Write};
+
+/// Target language of the include declaration.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Language {
+    C,
+    Rust,
+    Python,
+    Go,
+    Zig,
+}
+
+impl Language {
+    /// Derive an identifier from a file path in this language's naming style.
+    pub fn identifier(self, path: &str) -> String {
+        let mut name: String = path
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+            name.insert(0, '_');
+        }
+
+        match self {
+            Language::C | Language::Zig => name,
+            Language::Rust | Language::Python => name.to_ascii_uppercase(),
+            Language::Go => {
+                // lowerCamelCase, keeping a leading underscore for digit-led names
+                let mut out = String::with_capacity(name.len());
+                let mut upper = false;
+                for (i, c) in name.chars().enumerate() {
+                    if c == '_' && i > 0 {
+                        upper = true;
+                    } else if upper {
+                        out.push(c.to_ascii_uppercase());
+                        upper = false;
+                    } else {
+                        out.push(c);
+                    }
+                }
+                out
+            }
+        }
+    }
+}
+
+/// Literal syntax around the bytes of one declaration.
+struct Syntax {
+    /// Start of each line of items.
+    line_start: &'static str,
+    /// Between items on a line.
+    item_separator: &'static str,
+    /// Between two lines of items.
+    line_separator: &'static str,
+    /// After the last line of items.
+    last_line_end: &'static str,
+}
+
+const ARRAY_4: Syntax = Syntax {
+    line_start: "    ",
+    item_separator: ", ",
+    line_separator: ",\n",
+    last_line_end: ",\n",
+};
+
+impl Language {
+    fn syntax(self, string: bool) -> Syntax {
+        match (self, string) {
+            (Language::C, false) => Syntax {
+                line_start: "  ",
+                ..ARRAY_4
+            },
+            (Language::Go, false) => Syntax {
+                line_start: "\t",
+                ..ARRAY_4
+            },
+            (_, false) => ARRAY_4,
+            (Language::C, true) => Syntax {
+                line_start: "  \"",
+                item_separator: "",
+                line_separator: "\"\n",
+                last_line_end: "\";\n",
+            },
+            (Language::Rust, true) => Syntax {
+                line_start: "    ",
+                item_separator: "",
+                line_separator: "\\\n",
+                last_line_end: "\";\n",
+            },
+            (Language::Python, true) => Syntax {
+                line_start: "    b\"",
+                item_separator: "",
+                line_separator: "\"\n",
+                last_line_end: "\"\n",
+            },
+            (Language::Go, true) => Syntax {
+                line_start: "\t\"",
+                item_separator: "",
+                line_separator: "\" +\n",
+                last_line_end: "\",\n",
+            },
+            (Language::Zig, true) => Syntax {
+                line_start: "    \"",
+                item_separator: "",
+                line_separator: "\" ++\n",
+                last_line_end: "\";\n",
+            },
+        }
+    }
+
+    fn header(self, string: bool, name: &str) -> String {
+        match (self, string) {
+            (Language::C, false) => format!("unsigned char {}[] = {{\n", name),
+            (Language::C, true) => format!("unsigned char {}[] =\n", name),
+            (Language::Rust, false) => format!("pub const {}: &[u8] = &[\n", name),
+            (Language::Rust, true) => format!("pub const {}: &[u8] = b\"\\\n", name),
+            (Language::Python, false) => format!("{} = bytes([\n", name),
+            (Language::Python, true) => format!("{} = (\n", name),
+            (Language::Go, false) => format!("var {} = []byte{{\n", name),
+            (Language::Go, true) => format!("var {} = []byte(\n", name),
+            (Language::Zig, false) => format!("pub const {} = [_]u8{{\n", name),
+            (Language::Zig, true) => format!("pub const {} =\n", name),
+        }
+    }
+
+    fn footer(self, string: bool, name: &str, len: u64) -> String {
+        match (self, string) {
+            (Language::C, false) => format!("}};\nunsigned int {}_len = {};\n", name, len),
+            (Language::C, true) => format!("unsigned int {}_len = {};\n", name, len),
+            (Language::Rust, false) => format!("];\npub const {}_LEN: usize = {};\n", name, len),
+            (Language::Rust, true) => format!("pub const {}_LEN: usize = {};\n", name, len),
+            (Language::Python, false) => format!("])\n{}_LEN = {}\n", name, len),
+            (Language::Python, true) => format!(")\n{}_LEN = {}\n", name, len),
+            (Language::Go, false) => format!("}}\n\nconst {}Len = {}\n", name, len),
+            (Language::Go, true) => format!(")\n\nconst {}Len = {}\n", name, len),
+            (Language::Zig, false) => format!("}};\npub const {}_len = {};\n", name, len),
+            (Language::Zig, true) => format!("pub const {}_len = {};\n", name, len),
+        }
+    }
+}
+
+/// Include dumper: emits the input as a named array or string literal
+/// declaration, like `xxd -i`.
+pub struct IncludeDumper {
+    config: DumpConfig,
+    language: Language,
+    /// Emit a string literal of `\xNN` escapes instead of an array.
+    string: bool,
+    name: String,
+    syntax: Syntax,
+    line_buf: LineBuffer,
+    line_pos: usize,
+    len: u64,
+    started: bool,
+}
+
+impl IncludeDumper {
+    pub fn new(config: DumpConfig, language: Language, string: bool, name: String) -> Self {
+        Self {
+            config,
+            language,
+            string,
+            name,
+            syntax: language.syntax(string),
+            line_buf: LineBuffer::new(),
+            line_pos: 0,
+            len: 0,
+            started: false,
+        }
+    }
+
+    #[inline(always)]
+    fn write_header<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.started = true;
+        writer.write_all(self.language.header(self.string, &self.name).as_bytes())
+    }
+}
+
+impl Dumper for IncludeDumper {
+    #[inline]
+    fn process<W: Write>(&mut self, data: &[u8], writer: &mut W) -> io::Result<()> {
+        if !self.started {
+            self.write_header(writer)?;
+        }
+
+        for &byte in data {
+            if self.line_pos == 0 {
+                // The previous line is only terminated once we know it is not the last
+                if self.len > 0 {
+                    self.line_buf.extend(self.syntax.line_separator.as_bytes());
+                }
+                self.line_buf.extend(self.syntax.line_start.as_bytes());
+            } else {
+                self.line_buf.extend(self.syntax.item_separator.as_bytes());
+            }
+
+            let hex = &HEX_TABLE[byte as usize][..2];
+            self.line_buf
+                .extend(if self.string { b"\\x" } else { b"0x" });
+            self.line_buf.extend(hex);
+            self.line_pos += 1;
+            self.len += 1;
+
+            if self.line_pos >= self.config.bytes_per_line {
+                writer.write_all(self.line_buf.as_slice())?;
+                self.line_buf.reset();
+                self.line_pos = 0;
+            }
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn finish<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        if !self.started {
+            self.write_header(writer)?;
+        }
+
+        // An empty string literal still needs its quotes
+        if self.len == 0 && self.string {
+            self.line_buf.extend(self.syntax.line_start.as_bytes());
+        }
+        if self.len > 0 || self.string {
+            self.line_buf.extend(self.syntax.last_line_end.as_bytes());
+        }
+        writer.write_all(self.line_buf.as_slice())?;
+        self.line_buf.reset();
+
+        let footer = self.language.footer(self.string, &self.name, self.len);
+        writer.write_all(footer.as_bytes())
+    }
+}
@@ -1,146 +1,940 @@
-//! High-performance hex and binary conversion.
+//! High-performance hex, binary and text conversion.
 
+pub mod encode;
+pub mod highlight;
+pub mod include;
+pub mod parallel;
+pub mod records;
 pub mod tables;
 
-pub use tables::{BINARY_TABLE, BYTE_CLASS, ByteClass, HEX_TABLE, TEXT_ESCAPE};
+pub use encode::{EncodeDumper, Encoding, ASCII85_BASE};
+pub use highlight::{
+    load_rules, parse_pattern_rule, parse_range_rule, parse_value_rule, HighlightDumper,
+    Highlights, Rule,
+};
+pub use include::{IncludeDumper, Language};
+pub use parallel::ParallelDumper;
+pub use records::{IntelHexDumper, RecordFormat, SrecDumper, SrecType, MAX_RECORD_BYTES};
+pub use tables::{
+    ByteClass, BINARY_TABLE, BYTE_CLASS, BYTE_GRADIENT, DECIMAL_TABLE, HEX_TABLE, OCTAL_TABLE,
+    TEXT_ESCAPE, UPPER_HEX_TABLE,
+};
 
 use crate::output::buffer::LineBuffer;
 use crate::output::color;
+use crate::output::offset::{self, OffsetFormat};
+use crate::output::theme::Palette;
+use clap::ValueEnum;
+use std::fmt::Display;
 use std::io::{self, Write};
+use std::str::FromStr;
 
 /// Configuration for dumping.
+#[derive(Clone, Copy)]
 pub struct DumpConfig {
     pub bytes_per_line: usize,
+    /// Whether escape codes may be written at all (`--color`). When off
+    /// the output is plain text.
+    pub ansi: bool,
+    /// Color bytes by class; only set together with `ansi`.
     pub color_enabled: bool,
+    /// Escape codes of the color theme.
+    pub palette: &'static Palette,
+    /// Highlight rules, drawn over the class colors whenever `ansi` is set.
+    pub highlights: &'static Highlights,
     pub space_highlight: bool,
+    /// Show a character sidebar next to the hex columns (hex mode only).
+    pub ascii_column: bool,
+    /// Printed offset of the first byte: its file position plus any base address.
+    pub start_offset: u64,
+    pub offset_format: OffsetFormat,
+    /// Minimum number of digits in the offset column.
+    pub offset_width: usize,
+    /// Radix of the byte columns printed by `HexDumper`.
+    pub byte_format: ByteFormat,
+    /// Line layout of `HexDumper`.
+    pub layout: HexLayout,
+    /// Bytes per word in hex mode (1, 2, 4 or 8).
+    pub group_size: usize,
+    /// Byte order used to print grouped words.
+    pub endian: Endian,
+    /// Collapse runs of identical lines into a single `*` line (hex mode only).
+    pub squeeze: bool,
 }
 
-/// High-performance hex dumper.
+/// Radix of a byte column, each backed by a compile-time table.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum ByteFormat {
+    #[default]
+    Hex,
+    Octal,
+    Decimal,
+}
+
+impl ByteFormat {
+    /// Formatted byte followed by its separating space.
+    #[inline(always)]
+    fn cell(self, byte: u8) -> &'static [u8] {
+        match self {
+            ByteFormat::Hex => &HEX_TABLE[byte as usize],
+            ByteFormat::Octal => &OCTAL_TABLE[byte as usize],
+            ByteFormat::Decimal => &DECIMAL_TABLE[byte as usize],
+        }
+    }
+
+    /// Digits per byte, excluding the separator.
+    #[inline(always)]
+    fn digits(self) -> usize {
+        match self {
+            ByteFormat::Hex => 2,
+            ByteFormat::Octal | ByteFormat::Decimal => 3,
+        }
+    }
+}
+
+/// Width of a hex/octal/decimal column holding `bytes` bytes, each group
+/// followed by a space. A partial last group counts as full unless
+/// `short_groups`, as in xxd's layout.
+pub fn hex_column_width(
+    bytes: usize,
+    group_size: usize,
+    format: ByteFormat,
+    short_groups: bool,
+) -> usize {
+    if short_groups {
+        bytes * format.digits() + bytes.div_ceil(group_size)
+    } else {
+        bytes.div_ceil(group_size) * (group_size * format.digits() + 1)
+    }
+}
+
+/// Line layout of `HexDumper`.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum HexLayout {
+    /// `offset| bytes |chars|`
+    #[default]
+    Bred,
+    /// `offset: words  chars`, as printed by xxd.
+    Xxd,
+}
+
+impl HexLayout {
+    /// Written after the offset, followed by a space.
+    #[inline(always)]
+    fn offset_separator(self) -> &'static [u8] {
+        match self {
+            HexLayout::Bred => b"|",
+            HexLayout::Xxd => b":",
+        }
+    }
+
+    /// Written after the last byte column.
+    #[inline(always)]
+    fn hex_end(self) -> &'static [u8] {
+        match self {
+            HexLayout::Bred => b"|",
+            HexLayout::Xxd => b" ",
+        }
+    }
+
+    /// Written after the character sidebar, before the newline.
+    #[inline(always)]
+    fn ascii_end(self) -> &'static [u8] {
+        match self {
+            HexLayout::Bred => b"|",
+            HexLayout::Xxd => b"",
+        }
+    }
+}
+
+/// Byte order of multi-byte values.
+#[derive(Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Endian {
+    Little,
+    #[default]
+    Big,
+}
+
+/// Common interface of the dumpers, so input handling is written once.
+pub trait Dumper {
+    /// Process a chunk of bytes, writing formatted output.
+    fn process<W: Write>(&mut self, data: &[u8], writer: &mut W) -> io::Result<()>;
+
+    /// Finish processing and flush any remaining content.
+    fn finish<W: Write>(&mut self, writer: &mut W) -> io::Result<()>;
+
+    /// Process a chunk whose bytes carry highlight marks, one per byte:
+    /// 0 for none, otherwise the mark of the rule to draw the byte in.
+    /// Dumpers without colors ignore the marks.
+    fn process_marked<W: Write>(
+        &mut self,
+        data: &[u8],
+        marks: &[u8],
+        writer: &mut W,
+    ) -> io::Result<()> {
+        let _ = marks;
+        self.process(data, writer)
+    }
+
+    /// Process a hole of `len` zero bytes in a sparse file. Dumpers that can
+    /// summarize a hole instead of formatting every zero override this.
+    fn hole<W: Write>(&mut self, len: u64, writer: &mut W) -> io::Result<()> {
+        process_zeros(self, len, writer)
+    }
+
+    /// Skip `len` bytes that could not be read, such as unmapped pages of a
+    /// process. Dumpers without a way to mark them print zeros.
+    fn unreadable<W: Write>(&mut self, len: u64, writer: &mut W) -> io::Result<()> {
+        process_zeros(self, len, writer)
+    }
+
+    /// Write out a line still being filled, for input that has paused
+    /// such as a followed file. Later bytes start a new line. Dumpers
+    /// that hold nothing back do nothing.
+    fn flush_line<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        let _ = writer;
+        Ok(())
+    }
+}
+
+/// Process `len` zero bytes through `dumper`, a buffer at a time.
+fn process_zeros<D: Dumper + ?Sized, W: Write>(
+    dumper: &mut D,
+    len: u64,
+    writer: &mut W,
+) -> io::Result<()> {
+    static ZEROS: [u8; 64 * 1024] = [0u8; 64 * 1024];
+    let mut len = len;
+    while len > 0 {
+        let n = len.min(ZEROS.len() as u64) as usize;
+        dumper.process(&ZEROS[..n], writer)?;
+        len -= n as u64;
+    }
+    Ok(())
+}
+
+/// Highlight marks of the bytes being formatted. `NoMarks` stands in
+/// when there are none, so that path compiles as if marks did not exist.
+trait Marks: Copy {
+    /// Mark of byte `i`.
+    fn get(self, i: usize) -> u8;
+
+    /// Marks of bytes `start..end`.
+    fn slice(self, start: usize, end: usize) -> Self;
+
+    /// The first nonzero mark, or 0 if no byte is marked.
+    fn first(self) -> u8;
+}
+
+#[derive(Clone, Copy)]
+struct NoMarks;
+
+impl Marks for NoMarks {
+    #[inline(always)]
+    fn get(self, _: usize) -> u8 {
+        0
+    }
+
+    #[inline(always)]
+    fn slice(self, _: usize, _: usize) -> Self {
+        self
+    }
+
+    #[inline(always)]
+    fn first(self) -> u8 {
+        0
+    }
+}
+
+impl Marks for &[u8] {
+    #[inline(always)]
+    fn get(self, i: usize) -> u8 {
+        self[i]
+    }
+
+    #[inline(always)]
+    fn slice(self, start: usize, end: usize) -> Self {
+        &self[start..end]
+    }
+
+    #[inline(always)]
+    fn first(self) -> u8 {
+        self.iter().copied().find(|&mark| mark != 0).unwrap_or(0)
+    }
+}
+
+/// Color for a byte under the class-based coloring rules.
+#[inline(always)]
+fn byte_color(config: &DumpConfig, byte: u8) -> &'static [u8] {
+    let palette: &'static Palette = config.palette;
+    // Space highlighting works even without full color mode
+    if config.space_highlight && byte == 0x20 && config.ansi {
+        return &palette.highlight;
+    }
+    if !config.color_enabled {
+        return &[];
+    }
+    palette.byte(byte)
+}
+
+/// Color for a byte, from the highlight rule that marked it if any.
+#[inline(always)]
+fn marked_color(config: &DumpConfig, byte: u8, mark: u8) -> &'static [u8] {
+    if mark != 0 {
+        let highlights: &'static Highlights = config.highlights;
+        return highlights.escape(mark);
+    }
+    byte_color(config, byte)
+}
+
+/// Color of the offset column and markers, unless escape codes are off.
+#[inline(always)]
+fn offset_color(config: &DumpConfig) -> &'static [u8] {
+    let palette: &'static Palette = config.palette;
+    if config.ansi {
+        &palette.offset
+    } else {
+        &[]
+    }
+}
+
+/// Color of the `|` separators, unless escape codes are off.
+#[inline(always)]
+fn separator_color(config: &DumpConfig) -> &'static [u8] {
+    let palette: &'static Palette = config.palette;
+    if config.ansi {
+        &palette.separator
+    } else {
+        &[]
+    }
+}
+
+/// Push `bytes` wrapped in `col`, skipping the escape codes for uncolored bytes.
+#[inline(always)]
+fn push_colored(buf: &mut LineBuffer, col: &[u8], bytes: &[u8]) {
+    if col.is_empty() {
+        buf.extend(bytes);
+    } else {
+        buf.extend(col);
+        buf.extend(bytes);
+        buf.extend(color::RESET);
+    }
+}
+
+/// Push a column separator in `col`.
+#[inline(always)]
+fn push_separator(buf: &mut LineBuffer, col: &[u8], separator: &[u8]) {
+    if !separator.is_empty() {
+        push_colored(buf, col, separator);
+    }
+}
+
+/// Write a `-- what start..end --` line for bytes that are not printed.
+fn write_marker<W: Write>(
+    config: &DumpConfig,
+    what: &str,
+    start: u64,
+    end: u64,
+    writer: &mut W,
+) -> io::Result<()> {
+    let col = offset_color(config);
+    writer.write_all(col)?;
+    write!(writer, "-- {} {:#x}..{:#x} --", what, start, end)?;
+    if !col.is_empty() {
+        writer.write_all(color::RESET)?;
+    }
+    writeln!(writer)
+}
+
+/// Longest search match `write_match` prints in full.
+const MATCH_PREVIEW: usize = 32;
+
+/// Write a search match as a dump line: its offset and bytes, in the
+/// match color. Longer matches are cut short and followed by their length.
+pub fn write_match<W: Write>(
+    config: &DumpConfig,
+    offset: u64,
+    bytes: &[u8],
+    writer: &mut W,
+) -> io::Result<()> {
+    let mut buf = LineBuffer::new();
+    let col = offset_color(config);
+    buf.extend(col);
+    offset::push_offset(&mut buf, offset, config.offset_format, config.offset_width);
+    if !col.is_empty() {
+        buf.extend(color::RESET);
+    }
+    push_separator(&mut buf, separator_color(config), b"|");
+    buf.push(b' ');
+
+    let highlights: &'static Highlights = config.highlights;
+    let col = match highlights.match_mark() {
+        Some(mark) if config.ansi => highlights.escape(mark),
+        _ => &[],
+    };
+    for (i, &byte) in bytes.iter().take(MATCH_PREVIEW).enumerate() {
+        if i > 0 {
+            buf.push(b' ');
+        }
+        let cell = config.byte_format.cell(byte);
+        push_colored(&mut buf, col, &cell[..cell.len() - 1]);
+    }
+    writer.write_all(buf.as_slice())?;
+    if bytes.len() > MATCH_PREVIEW {
+        write!(writer, " ... ({} bytes)", bytes.len())?;
+    }
+    writeln!(writer)
+}
+
+/// High-performance hex dumper, also used for the octal and decimal byte modes.
 pub struct HexDumper {
     config: DumpConfig,
     line_buf: LineBuffer,
-    offset: usize,
+    ascii_buf: LineBuffer,
+    offset: u64,
     line_pos: usize,
+    /// Bytes of the word being assembled when grouping, and their marks.
+    group: [u8; 8],
+    group_marks: [u8; 8],
+    group_len: usize,
+    /// Whether the offset of the current line has been written.
+    line_open: bool,
+    started: bool,
+    /// Bytes of an incomplete line held back until it can be compared (squeeze only).
+    pending: Vec<u8>,
+    /// Marks of `pending`, or empty if none of its bytes are marked.
+    pending_marks: Vec<u8>,
+    /// Bytes of the last printed line (squeeze only).
+    previous: Vec<u8>,
+    /// Whether a `*` has been printed for the current run of repeated lines.
+    squeezing: bool,
 }
 
 impl HexDumper {
     pub fn new(config: DumpConfig) -> Self {
+        let line_capacity = if config.squeeze {
+            config.bytes_per_line
+        } else {
+            0
+        };
         Self {
+            pending: Vec::with_capacity(line_capacity),
+            pending_marks: Vec::new(),
+            previous: Vec::with_capacity(line_capacity),
+            squeezing: false,
+            offset: config.start_offset,
             config,
             line_buf: LineBuffer::new(),
-            offset: 0,
+            ascii_buf: LineBuffer::new(),
             line_pos: 0,
+            group: [0u8; 8],
+            group_marks: [0u8; 8],
+            group_len: 0,
+            line_open: false,
+            started: false,
         }
     }
 
-    /// Process a chunk of bytes, writing formatted output.
-    #[inline]
-    pub fn process<W: Write>(&mut self, data: &[u8], writer: &mut W) -> io::Result<()> {
-        // Write initial offset if this is the start
-        if self.offset == 0 && self.line_pos == 0 {
+    #[inline(always)]
+    fn process_bytes<M: Marks, W: Write>(
+        &mut self,
+        data: &[u8],
+        marks: M,
+        writer: &mut W,
+    ) -> io::Result<()> {
+        for (i, &byte) in data.iter().enumerate() {
+            self.process_byte(byte, marks.get(i), writer)?;
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn process_byte<W: Write>(&mut self, byte: u8, mark: u8, writer: &mut W) -> io::Result<()> {
+        if !self.line_open {
+            self.line_open = true;
             self.write_offset(writer)?;
         }
 
-        for &byte in data {
-            self.process_byte(byte, writer)?;
+        let col = self.color(byte, mark);
+
+        if self.config.group_size == 1 {
+            push_colored(&mut self.line_buf, col, self.config.byte_format.cell(byte));
+        } else {
+            self.group[self.group_len] = byte;
+            self.group_marks[self.group_len] = mark;
+            self.group_len += 1;
+            if self.group_len == self.config.group_size {
+                self.write_group();
+            }
+        }
+
+        if self.config.ascii_column {
+            self.push_ascii(byte, col);
+        }
+
+        self.line_pos += 1;
+
+        if self.line_pos >= self.config.bytes_per_line {
+            // Lines that are not a whole number of groups end in a short group
+            if self.group_len > 0 {
+                self.write_group();
+            }
+
+            self.push_hex_end();
+            if self.config.ascii_column {
+                writer.write_all(self.line_buf.as_slice())?;
+                self.line_buf.reset();
+                self.write_ascii(writer)?;
+            } else {
+                self.line_buf.push(b'\n');
+                writer.write_all(self.line_buf.as_slice())?;
+                self.line_buf.reset();
+            }
+            self.offset = self.offset.wrapping_add(self.line_pos as u64);
+            self.line_pos = 0;
+            self.line_open = false;
         }
 
         Ok(())
     }
 
-    /// Finish processing and flush any remaining content.
-    #[inline]
-    pub fn finish<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
-        if self.line_buf.len() > 0 {
+    /// Print a full line, or a `*` if it repeats the previous one.
+    /// Highlighted lines are always printed.
+    #[inline(always)]
+    fn squeeze_line<M: Marks, W: Write>(
+        &mut self,
+        line: &[u8],
+        marks: M,
+        writer: &mut W,
+    ) -> io::Result<()> {
+        if line == self.previous.as_slice() && marks.first() == 0 {
+            if !self.squeezing {
+                self.squeezing = true;
+                writer.write_all(b"*\n")?;
+            }
+            self.offset = self.offset.wrapping_add(line.len() as u64);
+            return Ok(());
+        }
+
+        self.squeezing = false;
+        self.process_bytes(line, marks, writer)?;
+        self.previous.clear();
+        self.previous.extend_from_slice(line);
+        Ok(())
+    }
+
+    /// Hold back bytes of an incomplete line (squeeze only).
+    #[inline(always)]
+    fn hold<M: Marks>(&mut self, bytes: &[u8], marks: M) {
+        if marks.first() != 0 || !self.pending_marks.is_empty() {
+            self.pending_marks.resize(self.pending.len(), 0);
+            self.pending_marks
+                .extend((0..bytes.len()).map(|i| marks.get(i)));
+        }
+        self.pending.extend_from_slice(bytes);
+    }
+
+    /// Print the held back bytes, as a squeezable line if `squeeze`.
+    fn flush_pending<W: Write>(&mut self, squeeze: bool, writer: &mut W) -> io::Result<()> {
+        let pending = std::mem::take(&mut self.pending);
+        let marks = std::mem::take(&mut self.pending_marks);
+        let result = match (squeeze, marks.is_empty()) {
+            (true, true) => self.squeeze_line(&pending, NoMarks, writer),
+            (true, false) => self.squeeze_line(&pending, &marks[..], writer),
+            (false, true) => self.process_bytes(&pending, NoMarks, writer),
+            (false, false) => self.process_bytes(&pending, &marks[..], writer),
+        };
+        self.pending = pending;
+        self.pending.clear();
+        self.pending_marks = marks;
+        self.pending_marks.clear();
+        result
+    }
+
+    /// Write a `-- what start..end --` line for `len` bytes that are not
+    /// printed, at the start of a line, and move past them.
+    fn write_marker<W: Write>(&mut self, what: &str, len: u64, writer: &mut W) -> io::Result<()> {
+        self.started = true;
+        let start = self.offset;
+        self.offset = self.offset.wrapping_add(len);
+        write_marker(&self.config, what, start, self.offset, writer)?;
+
+        // The line after a marker is always printed, so its offset ends the run
+        self.previous.clear();
+        self.squeezing = false;
+        Ok(())
+    }
+
+    /// Print the held back and partial line as it stands, so that the next
+    /// byte starts a new line.
+    fn close_line<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.flush_pending(false, writer)?;
+
+        if self.line_pos == 0 {
+            return Ok(());
+        }
+        if self.group_len > 0 {
+            self.write_group();
+        }
+        if self.config.ascii_column {
+            let pad = self.hex_width(self.config.bytes_per_line) - self.hex_width(self.line_pos);
+            for _ in 0..pad {
+                self.line_buf.push(b' ');
+            }
+            self.push_hex_end();
+            writer.write_all(self.line_buf.as_slice())?;
+            self.line_buf.reset();
+            self.write_ascii(writer)?;
+        } else {
+            self.line_buf.push(b'\n');
             writer.write_all(self.line_buf.as_slice())?;
             self.line_buf.reset();
         }
-        writeln!(writer)?;
+        self.offset = self.offset.wrapping_add(self.line_pos as u64);
+        self.line_pos = 0;
+        self.line_open = false;
         Ok(())
     }
 
+    /// Color for a byte in both the hex and character columns.
     #[inline(always)]
-    fn process_byte<W: Write>(&mut self, byte: u8, writer: &mut W) -> io::Result<()> {
-        if self.config.color_enabled {
-            self.write_colored_hex(byte);
-        } else if self.config.space_highlight && byte == 0x20 {
-            // Space highlighting works even without full color mode
-            self.line_buf.extend(color::GREEN);
-            self.line_buf.extend(&HEX_TABLE[byte as usize]);
-            self.line_buf.extend(color::RESET);
+    fn color(&self, byte: u8, mark: u8) -> &'static [u8] {
+        marked_color(&self.config, byte, mark)
+    }
+
+    /// Write the buffered group as one word, padding a short group to full
+    /// width unless `short_groups`. Little-endian groups print their last
+    /// byte first, like `xxd -e`.
+    #[inline(always)]
+    fn write_group(&mut self) {
+        let missing = if self.short_groups() {
+            0
+        } else {
+            self.config.group_size - self.group_len
+        };
+
+        if self.config.endian == Endian::Little {
+            for _ in 0..missing {
+                self.line_buf.extend(b"  ");
+            }
+            for i in (0..self.group_len).rev() {
+                let byte = self.group[i];
+                let col = self.color(byte, self.group_marks[i]);
+                push_colored(&mut self.line_buf, col, &HEX_TABLE[byte as usize][..2]);
+            }
         } else {
-            self.line_buf.extend(&HEX_TABLE[byte as usize]);
+            for i in 0..self.group_len {
+                let byte = self.group[i];
+                let col = self.color(byte, self.group_marks[i]);
+                push_colored(&mut self.line_buf, col, &HEX_TABLE[byte as usize][..2]);
+            }
+            for _ in 0..missing {
+                self.line_buf.extend(b"  ");
+            }
         }
 
-        self.line_pos += 1;
+        self.line_buf.push(b' ');
+        self.group_len = 0;
+    }
 
-        if self.line_pos >= self.config.bytes_per_line {
-            self.line_buf.extend(b"|\n");
+    /// Whether a short last group keeps its own width, as xxd prints it.
+    /// `xxd -e` pads it like bred's layout does.
+    #[inline(always)]
+    fn short_groups(&self) -> bool {
+        self.config.layout == HexLayout::Xxd && self.config.endian == Endian::Big
+    }
+
+    #[inline(always)]
+    fn hex_width(&self, bytes: usize) -> usize {
+        hex_column_width(
+            bytes,
+            self.config.group_size,
+            self.config.byte_format,
+            self.short_groups(),
+        )
+    }
+
+    #[inline(always)]
+    fn push_ascii(&mut self, byte: u8, col: &[u8]) {
+        let ch = if self.config.space_highlight && byte == 0x20 {
+            b'_'
+        } else if BYTE_CLASS[byte as usize] == ByteClass::Printable {
+            byte
+        } else {
+            b'.'
+        };
+        push_colored(&mut self.ascii_buf, col, &[ch]);
+    }
+
+    /// Write the character sidebar for the current line and end the line.
+    #[inline(always)]
+    fn write_ascii<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        let col = self.separator_color();
+        push_separator(&mut self.ascii_buf, col, self.config.layout.ascii_end());
+        self.ascii_buf.push(b'\n');
+        writer.write_all(self.ascii_buf.as_slice())?;
+        self.ascii_buf.reset();
+        Ok(())
+    }
+
+    /// Color for the offset column or separators. xxd output must stay
+    /// plain text for scripts unless color is asked for.
+    #[inline(always)]
+    fn frame_color(&self, col: &'static [u8]) -> &'static [u8] {
+        if self.config.layout == HexLayout::Xxd && !self.config.color_enabled {
+            &[]
+        } else {
+            col
+        }
+    }
+
+    #[inline(always)]
+    fn separator_color(&self) -> &'static [u8] {
+        self.frame_color(separator_color(&self.config))
+    }
+
+    /// End the byte columns of a line.
+    #[inline(always)]
+    fn push_hex_end(&mut self) {
+        let col = self.separator_color();
+        push_separator(&mut self.line_buf, col, self.config.layout.hex_end());
+    }
+
+    #[inline(always)]
+    fn write_offset<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        let col = self.frame_color(offset_color(&self.config));
+        self.line_buf.extend(col);
+        offset::push_offset(
+            &mut self.line_buf,
+            self.offset,
+            self.config.offset_format,
+            self.config.offset_width,
+        );
+        if !col.is_empty() {
+            self.line_buf.extend(color::RESET);
+        }
+        let col = self.separator_color();
+        push_separator(
+            &mut self.line_buf,
+            col,
+            self.config.layout.offset_separator(),
+        );
+        self.line_buf.push(b' ');
+        writer.write_all(self.line_buf.as_slice())?;
+        self.line_buf.reset();
+        Ok(())
+    }
+}
+
+impl HexDumper {
+    #[inline(always)]
+    fn process_chunk<M: Marks, W: Write>(
+        &mut self,
+        data: &[u8],
+        marks: M,
+        writer: &mut W,
+    ) -> io::Result<()> {
+        self.started = true;
+
+        if !self.config.squeeze {
+            return self.process_bytes(data, marks, writer);
+        }
+
+        // Whole lines are compared before printing, so complete the line
+        // carried over from the previous chunk first
+        let bytes_per_line = self.config.bytes_per_line;
+        let (mut data, mut marks) = (data, marks);
+        if !self.pending.is_empty() {
+            let take = (bytes_per_line - self.pending.len()).min(data.len());
+            self.hold(&data[..take], marks.slice(0, take));
+            marks = marks.slice(take, data.len());
+            data = &data[take..];
+            if self.pending.len() < bytes_per_line {
+                return Ok(());
+            }
+            self.flush_pending(true, writer)?;
+        }
+
+        let mut lines = data.chunks_exact(bytes_per_line);
+        for (i, line) in (&mut lines).enumerate() {
+            let start = i * bytes_per_line;
+            self.squeeze_line(line, marks.slice(start, start + bytes_per_line), writer)?;
+        }
+        let rest = data.len() - lines.remainder().len();
+        self.hold(lines.remainder(), marks.slice(rest, data.len()));
+
+        Ok(())
+    }
+}
+
+impl Dumper for HexDumper {
+    #[inline]
+    fn process<W: Write>(&mut self, data: &[u8], writer: &mut W) -> io::Result<()> {
+        self.process_chunk(data, NoMarks, writer)
+    }
+
+    #[inline]
+    fn process_marked<W: Write>(
+        &mut self,
+        data: &[u8],
+        marks: &[u8],
+        writer: &mut W,
+    ) -> io::Result<()> {
+        self.process_chunk(data, marks, writer)
+    }
+
+    /// Skip the whole lines of a hole with a single `-- hole start..end --`
+    /// line, or leave them out in xxd's layout. Bytes before the first and
+    /// after the last whole line are printed as zeros so every line keeps
+    /// its alignment.
+    fn hole<W: Write>(&mut self, len: u64, writer: &mut W) -> io::Result<()> {
+        let bytes_per_line = self.config.bytes_per_line as u64;
+        let lead = match (self.line_pos + self.pending.len()) as u64 % bytes_per_line {
+            0 => 0,
+            used => (bytes_per_line - used).min(len),
+        };
+        process_zeros(self, lead, writer)?;
+
+        let skipped = (len - lead) / bytes_per_line * bytes_per_line;
+        if skipped > 0 && self.config.layout == HexLayout::Xxd {
+            // xxd has no markers; `xxd -r` fills the jump in offsets with zeros
+            self.offset = self.offset.wrapping_add(skipped);
+        } else if skipped > 0 {
+            self.started = true;
+            self.write_marker("hole", skipped, writer)?;
+        }
+
+        process_zeros(self, len - lead - skipped, writer)
+    }
+
+    /// End the current line early and mark the unreadable bytes with a
+    /// `-- unreadable start..end --` line; they are never shown as zeros.
+    fn unreadable<W: Write>(&mut self, len: u64, writer: &mut W) -> io::Result<()> {
+        self.close_line(writer)?;
+        self.write_marker("unreadable", len, writer)
+    }
+
+    fn flush_line<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.close_line(writer)
+    }
+
+    #[inline]
+    fn finish<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        // A final partial line is never squeezed
+        self.flush_pending(false, writer)?;
+
+        // bred ends with the offset just past the data; xxd does not
+        if self.started && !self.line_open && self.config.layout == HexLayout::Bred {
+            self.write_offset(writer)?;
+        }
+
+        if self.group_len > 0 {
+            self.write_group();
+        }
+
+        if self.config.ascii_column && self.line_pos > 0 {
+            // Pad the missing hex cells so the sidebar lines up with full lines
+            let pad = self.hex_width(self.config.bytes_per_line) - self.hex_width(self.line_pos);
+            for _ in 0..pad {
+                self.line_buf.push(b' ');
+            }
+            self.push_hex_end();
             writer.write_all(self.line_buf.as_slice())?;
             self.line_buf.reset();
-            self.offset += self.line_pos;
-            self.line_pos = 0;
-            self.write_offset(writer)?;
+            self.write_ascii(writer)?;
+            return Ok(());
         }
 
+        // xxd never leaves an empty line at the end
+        if self.config.layout == HexLayout::Xxd {
+            if self.line_buf.len() > 0 {
+                self.line_buf.push(b'\n');
+                writer.write_all(self.line_buf.as_slice())?;
+                self.line_buf.reset();
+            }
+            return Ok(());
+        }
+        if self.line_buf.len() > 0 {
+            writer.write_all(self.line_buf.as_slice())?;
+            self.line_buf.reset();
+        }
+        writeln!(writer)?;
         Ok(())
     }
+}
+
+/// Plain hex dumper: continuous hex digits without offsets or separators,
+/// like `xxd -p`.
+pub struct PlainHexDumper {
+    config: DumpConfig,
+    line_buf: LineBuffer,
+    line_pos: usize,
+}
+
+impl PlainHexDumper {
+    pub fn new(config: DumpConfig) -> Self {
+        Self {
+            config,
+            line_buf: LineBuffer::new(),
+            line_pos: 0,
+        }
+    }
+}
 
+impl PlainHexDumper {
     #[inline(always)]
-    fn write_colored_hex(&mut self, byte: u8) {
-        let class = BYTE_CLASS[byte as usize];
-        let is_space = self.config.space_highlight && byte == 0x20;
+    fn process_chunk<M: Marks, W: Write>(
+        &mut self,
+        data: &[u8],
+        marks: M,
+        writer: &mut W,
+    ) -> io::Result<()> {
+        for (i, &byte) in data.iter().enumerate() {
+            let col = marked_color(&self.config, byte, marks.get(i));
+            push_colored(&mut self.line_buf, col, &HEX_TABLE[byte as usize][..2]);
+            self.line_pos += 1;
 
-        if is_space {
-            self.line_buf.extend(color::GREEN);
-            self.line_buf.extend(&HEX_TABLE[byte as usize]);
-            self.line_buf.extend(color::RESET);
-        } else {
-            match class {
-                ByteClass::Null => {
-                    self.line_buf.extend(color::GRAY);
-                    self.line_buf.extend(&HEX_TABLE[byte as usize]);
-                    self.line_buf.extend(color::RESET);
-                }
-                ByteClass::Control => {
-                    self.line_buf.extend(color::RED);
-                    self.line_buf.extend(&HEX_TABLE[byte as usize]);
-                    self.line_buf.extend(color::RESET);
-                }
-                ByteClass::Printable => {
-                    self.line_buf.extend(&HEX_TABLE[byte as usize]);
-                }
-                ByteClass::Extended => {
-                    self.line_buf.extend(color::ORANGE);
-                    self.line_buf.extend(&HEX_TABLE[byte as usize]);
-                    self.line_buf.extend(color::RESET);
-                }
+            if self.line_pos >= self.config.bytes_per_line {
+                self.line_buf.push(b'\n');
+                writer.write_all(self.line_buf.as_slice())?;
+                self.line_buf.reset();
+                self.line_pos = 0;
             }
         }
-    }
 
-    #[inline(always)]
-    fn write_offset<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
-        self.line_buf.extend(color::GRAY);
-        self.push_offset_hex();
-        self.line_buf.extend(color::RESET);
-        self.line_buf.extend(b"| ");
-        writer.write_all(self.line_buf.as_slice())?;
-        self.line_buf.reset();
         Ok(())
     }
+}
 
-    #[inline(always)]
-    fn push_offset_hex(&mut self) {
-        let off = self.offset;
-        const HEX: &[u8] = b"0123456789abcdef";
-        self.line_buf.push(HEX[(off >> 24) & 0xF]);
-        self.line_buf.push(HEX[(off >> 20) & 0xF]);
-        self.line_buf.push(HEX[(off >> 16) & 0xF]);
-        self.line_buf.push(HEX[(off >> 12) & 0xF]);
-        self.line_buf.push(HEX[(off >> 8) & 0xF]);
-        self.line_buf.push(HEX[(off >> 4) & 0xF]);
-        self.line_buf.push(HEX[off & 0xF]);
+impl Dumper for PlainHexDumper {
+    #[inline]
+    fn process<W: Write>(&mut self, data: &[u8], writer: &mut W) -> io::Result<()> {
+        self.process_chunk(data, NoMarks, writer)
+    }
+
+    #[inline]
+    fn process_marked<W: Write>(
+        &mut self,
+        data: &[u8],
+        marks: &[u8],
+        writer: &mut W,
+    ) -> io::Result<()> {
+        self.process_chunk(data, marks, writer)
+    }
+
+    #[inline]
+    fn finish<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.flush_line(writer)
+    }
+
+    fn flush_line<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        if self.line_pos > 0 {
+            self.line_buf.push(b'\n');
+            writer.write_all(self.line_buf.as_slice())?;
+            self.line_buf.reset();
+            self.line_pos = 0;
+        }
+        Ok(())
     }
 }
 
@@ -148,49 +942,51 @@ impl HexDumper {
 pub struct BinaryDumper {
     config: DumpConfig,
     line_buf: LineBuffer,
-    offset: usize,
+    offset: u64,
     bit_pos: usize,
+    /// Bits emitted on completed lines, so offsets stay exact when
+    /// lines do not end on a byte boundary.
+    bit_count: u64,
+    started: bool,
 }
 
 impl BinaryDumper {
     pub fn new(config: DumpConfig) -> Self {
         Self {
+            offset: config.start_offset,
             config,
             line_buf: LineBuffer::new(),
-            offset: 0,
             bit_pos: 0,
+            bit_count: 0,
+            started: false,
         }
     }
 
-    #[inline]
-    pub fn process<W: Write>(&mut self, data: &[u8], writer: &mut W) -> io::Result<()> {
-        if self.offset == 0 && self.bit_pos == 0 {
-            self.write_offset(writer)?;
+    #[inline(always)]
+    fn process_chunk<M: Marks, W: Write>(
+        &mut self,
+        data: &[u8],
+        marks: M,
+        writer: &mut W,
+    ) -> io::Result<()> {
+        if !self.started {
+            self.started = true;
+            self.push_offset();
         }
 
-        for &byte in data {
-            self.process_byte(byte, writer)?;
+        for (i, &byte) in data.iter().enumerate() {
+            self.process_byte(byte, marks.get(i), writer)?;
         }
 
         Ok(())
     }
 
-    #[inline]
-    pub fn finish<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
-        if self.line_buf.len() > 0 {
-            writer.write_all(self.line_buf.as_slice())?;
-            self.line_buf.reset();
-        }
-        writeln!(writer)?;
-        Ok(())
-    }
-
     #[inline(always)]
-    fn process_byte<W: Write>(&mut self, byte: u8, writer: &mut W) -> io::Result<()> {
+    fn process_byte<W: Write>(&mut self, byte: u8, mark: u8, writer: &mut W) -> io::Result<()> {
         let bits = &BINARY_TABLE[byte as usize];
 
-        if self.config.color_enabled {
-            self.write_colored_binary(byte, bits);
+        if self.config.color_enabled || mark != 0 {
+            self.write_colored_binary(byte, mark, bits, writer)?;
         } else {
             // Write each bit, checking for line wrap
             for &bit in bits {
@@ -203,73 +999,137 @@ impl BinaryDumper {
             }
         }
 
-        if !self.config.color_enabled {
-            return Ok(());
-        }
-
-        // Color mode already handles line wrapping
         Ok(())
     }
 
     #[inline(always)]
-    fn write_colored_binary(&mut self, byte: u8, bits: &[u8; 8]) {
-        let class = BYTE_CLASS[byte as usize];
-        let is_space = self.config.space_highlight && byte == 0x20;
+    fn write_colored_binary<W: Write>(
+        &mut self,
+        byte: u8,
+        mark: u8,
+        bits: &[u8; 8],
+        writer: &mut W,
+    ) -> io::Result<()> {
+        let col = marked_color(&self.config, byte, mark);
 
-        let col = if is_space {
-            color::GREEN
-        } else {
-            match class {
-                ByteClass::Null => color::GRAY,
-                ByteClass::Control => color::RED,
-                ByteClass::Printable => &[],
-                ByteClass::Extended => color::ORANGE,
-            }
-        };
+        // A byte may straddle two lines; color each part separately
+        let mut start = 0;
+        while start < bits.len() {
+            let room = self.config.bytes_per_line - self.bit_pos;
+            let end = bits.len().min(start + room);
+            push_colored(&mut self.line_buf, col, &bits[start..end]);
+            self.bit_pos += end - start;
+            start = end;
 
-        if !col.is_empty() {
-            self.line_buf.extend(col);
-        }
-        self.line_buf.extend(bits);
-        if !col.is_empty() {
-            self.line_buf.extend(color::RESET);
+            if self.bit_pos >= self.config.bytes_per_line {
+                self.end_line(writer)?;
+            }
         }
-        self.bit_pos += 8;
+
+        Ok(())
     }
 
     #[inline(always)]
     fn end_line<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
-        self.line_buf.extend(b" |\n");
+        self.line_buf.push(b' ');
+        push_separator(&mut self.line_buf, separator_color(&self.config), b"|");
+        self.line_buf.push(b'\n');
         writer.write_all(self.line_buf.as_slice())?;
         self.line_buf.reset();
-        self.offset += self.bit_pos / 8;
+        self.bit_count += self.bit_pos as u64;
+        self.offset = self.config.start_offset.wrapping_add(self.bit_count / 8);
         self.bit_pos = 0;
-        self.write_offset(writer)?;
+        self.push_offset();
         Ok(())
     }
 
-    #[inline(always)]
-    fn write_offset<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
-        self.line_buf.extend(color::GRAY);
-        self.push_offset_hex();
-        self.line_buf.extend(color::RESET);
-        self.line_buf.extend(b"| ");
-        writer.write_all(self.line_buf.as_slice())?;
+    /// End the current line early and write a marker line for `len` bytes
+    /// that are not printed. The next line starts after them.
+    fn skip<W: Write>(&mut self, what: &str, len: u64, writer: &mut W) -> io::Result<()> {
+        if self.bit_pos > 0 {
+            self.line_buf.push(b'\n');
+            writer.write_all(self.line_buf.as_slice())?;
+        }
+        // A line without bits is dropped along with its offset
         self.line_buf.reset();
+
+        let start = self
+            .config
+            .start_offset
+            .wrapping_add((self.bit_count + self.bit_pos as u64) / 8);
+        let end = start.wrapping_add(len);
+        write_marker(&self.config, what, start, end, writer)?;
+        self.config.start_offset = end;
+        self.offset = end;
+        self.bit_count = 0;
+        self.bit_pos = 0;
+        self.started = true;
+        self.push_offset();
         Ok(())
     }
 
+    /// Start a line with its offset; it is written out with the line.
     #[inline(always)]
-    fn push_offset_hex(&mut self) {
-        let off = self.offset;
-        const HEX: &[u8] = b"0123456789abcdef";
-        self.line_buf.push(HEX[(off >> 24) & 0xF]);
-        self.line_buf.push(HEX[(off >> 20) & 0xF]);
-        self.line_buf.push(HEX[(off >> 16) & 0xF]);
-        self.line_buf.push(HEX[(off >> 12) & 0xF]);
-        self.line_buf.push(HEX[(off >> 8) & 0xF]);
-        self.line_buf.push(HEX[(off >> 4) & 0xF]);
-        self.line_buf.push(HEX[off & 0xF]);
+    fn push_offset(&mut self) {
+        let col = offset_color(&self.config);
+        self.line_buf.extend(col);
+        offset::push_offset(
+            &mut self.line_buf,
+            self.offset,
+            self.config.offset_format,
+            self.config.offset_width,
+        );
+        if !col.is_empty() {
+            self.line_buf.extend(color::RESET);
+        }
+        push_separator(&mut self.line_buf, separator_color(&self.config), b"|");
+        self.line_buf.push(b' ');
+    }
+}
+
+impl Dumper for BinaryDumper {
+    #[inline]
+    fn process<W: Write>(&mut self, data: &[u8], writer: &mut W) -> io::Result<()> {
+        self.process_chunk(data, NoMarks, writer)
+    }
+
+    #[inline]
+    fn process_marked<W: Write>(
+        &mut self,
+        data: &[u8],
+        marks: &[u8],
+        writer: &mut W,
+    ) -> io::Result<()> {
+        self.process_chunk(data, marks, writer)
+    }
+
+    /// Skip a hole with a single `-- hole start..end --` line.
+    fn hole<W: Write>(&mut self, len: u64, writer: &mut W) -> io::Result<()> {
+        self.skip("hole", len, writer)
+    }
+
+    /// End the current line early and mark the unreadable bytes with a
+    /// `-- unreadable start..end --` line; they are never shown as zeros.
+    fn unreadable<W: Write>(&mut self, len: u64, writer: &mut W) -> io::Result<()> {
+        self.skip("unreadable", len, writer)
+    }
+
+    /// End the current line early like a full one.
+    fn flush_line<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        if self.bit_pos > 0 {
+            self.end_line(writer)?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn finish<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        if self.line_buf.len() > 0 {
+            writer.write_all(self.line_buf.as_slice())?;
+            self.line_buf.reset();
+        }
+        writeln!(writer)?;
+        Ok(())
     }
 }
 
@@ -277,45 +1137,46 @@ impl BinaryDumper {
 pub struct TextDumper {
     config: DumpConfig,
     line_buf: LineBuffer,
-    offset: usize,
+    offset: u64,
     char_pos: usize,
+    /// Input bytes on the current line; escapes make this differ from `char_pos`.
+    line_bytes: usize,
+    started: bool,
 }
 
 impl TextDumper {
     pub fn new(config: DumpConfig) -> Self {
         Self {
+            offset: config.start_offset,
             config,
             line_buf: LineBuffer::new(),
-            offset: 0,
             char_pos: 0,
+            line_bytes: 0,
+            started: false,
         }
     }
 
-    #[inline]
-    pub fn process<W: Write>(&mut self, data: &[u8], writer: &mut W) -> io::Result<()> {
-        if self.offset == 0 && self.char_pos == 0 {
-            self.write_offset(writer)?;
+    #[inline(always)]
+    fn process_chunk<M: Marks, W: Write>(
+        &mut self,
+        data: &[u8],
+        marks: M,
+        writer: &mut W,
+    ) -> io::Result<()> {
+        if !self.started {
+            self.started = true;
+            self.push_offset();
         }
 
-        for &byte in data {
-            self.process_byte(byte, writer)?;
+        for (i, &byte) in data.iter().enumerate() {
+            self.process_byte(byte, marks.get(i), writer)?;
         }
 
         Ok(())
     }
 
-    #[inline]
-    pub fn finish<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
-        if self.line_buf.len() > 0 {
-            writer.write_all(self.line_buf.as_slice())?;
-            self.line_buf.reset();
-        }
-        writeln!(writer)?;
-        Ok(())
-    }
-
     #[inline(always)]
-    fn process_byte<W: Write>(&mut self, byte: u8, writer: &mut W) -> io::Result<()> {
+    fn process_byte<W: Write>(&mut self, byte: u8, mark: u8, writer: &mut W) -> io::Result<()> {
         let (escaped, len) = TEXT_ESCAPE[byte as usize];
         let len = len as usize;
 
@@ -324,17 +1185,17 @@ impl TextDumper {
             self.end_line(writer)?;
         }
 
-        if self.config.color_enabled {
-            self.write_colored_text(byte, &escaped[..len]);
+        if self.config.color_enabled || mark != 0 {
+            self.write_colored_text(byte, mark, &escaped[..len]);
         } else if self.config.space_highlight && byte == b' ' {
-            self.line_buf.extend(color::GREEN);
-            self.line_buf.push(b'_');
-            self.line_buf.extend(color::RESET);
+            let col = byte_color(&self.config, byte);
+            push_colored(&mut self.line_buf, col, b"_");
         } else {
             self.line_buf.extend(&escaped[..len]);
         }
 
         self.char_pos += len;
+        self.line_bytes += 1;
 
         // Check again after writing
         if self.char_pos >= self.config.bytes_per_line {
@@ -345,64 +1206,481 @@ impl TextDumper {
     }
 
     #[inline(always)]
-    fn write_colored_text(&mut self, byte: u8, escaped: &[u8]) {
-        let class = BYTE_CLASS[byte as usize];
-        let is_space = self.config.space_highlight && byte == 0x20;
-
-        if is_space {
-            self.line_buf.extend(color::GREEN);
-            self.line_buf.push(b'_');
-            self.line_buf.extend(color::RESET);
+    fn write_colored_text(&mut self, byte: u8, mark: u8, escaped: &[u8]) {
+        let col = marked_color(&self.config, byte, mark);
+        if self.config.space_highlight && byte == 0x20 {
+            push_colored(&mut self.line_buf, col, b"_");
         } else {
-            let col = match class {
-                ByteClass::Null => color::GRAY,
-                ByteClass::Control => color::RED,
-                ByteClass::Printable => &[],
-                ByteClass::Extended => color::ORANGE,
-            };
-
-            if !col.is_empty() {
-                self.line_buf.extend(col);
-            }
-            self.line_buf.extend(escaped);
-            if !col.is_empty() {
-                self.line_buf.extend(color::RESET);
-            }
+            push_colored(&mut self.line_buf, col, escaped);
         }
     }
 
     #[inline(always)]
     fn end_line<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
-        self.line_buf.extend(b" |\n");
+        self.line_buf.push(b' ');
+        push_separator(&mut self.line_buf, separator_color(&self.config), b"|");
+        self.line_buf.push(b'\n');
         writer.write_all(self.line_buf.as_slice())?;
         self.line_buf.reset();
-        self.offset += self.char_pos;
+        self.offset = self.offset.wrapping_add(self.line_bytes as u64);
         self.char_pos = 0;
-        self.write_offset(writer)?;
+        self.line_bytes = 0;
+        self.push_offset();
+        Ok(())
+    }
+
+    /// End the current line early and write a marker line for `len` bytes
+    /// that are not printed. The next line starts after them.
+    fn skip<W: Write>(&mut self, what: &str, len: u64, writer: &mut W) -> io::Result<()> {
+        if self.char_pos > 0 {
+            self.line_buf.push(b' ');
+            push_separator(&mut self.line_buf, separator_color(&self.config), b"|");
+            self.line_buf.push(b'\n');
+            writer.write_all(self.line_buf.as_slice())?;
+            self.offset = self.offset.wrapping_add(self.line_bytes as u64);
+            self.char_pos = 0;
+            self.line_bytes = 0;
+        }
+        // A line without bytes is dropped along with its offset
+        self.line_buf.reset();
+
+        let start = self.offset;
+        self.offset = start.wrapping_add(len);
+        write_marker(&self.config, what, start, self.offset, writer)?;
+        self.started = true;
+        self.push_offset();
         Ok(())
     }
 
+    /// Start a line with its offset; it is written out with the line.
     #[inline(always)]
-    fn write_offset<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
-        self.line_buf.extend(color::GRAY);
-        self.push_offset_hex();
-        self.line_buf.extend(color::RESET);
-        self.line_buf.extend(b"| ");
+    fn push_offset(&mut self) {
+        let col = offset_color(&self.config);
+        self.line_buf.extend(col);
+        offset::push_offset(
+            &mut self.line_buf,
+            self.offset,
+            self.config.offset_format,
+            self.config.offset_width,
+        );
+        if !col.is_empty() {
+            self.line_buf.extend(color::RESET);
+        }
+        push_separator(&mut self.line_buf, separator_color(&self.config), b"|");
+        self.line_buf.push(b' ');
+    }
+}
+
+impl Dumper for TextDumper {
+    #[inline]
+    fn process<W: Write>(&mut self, data: &[u8], writer: &mut W) -> io::Result<()> {
+        self.process_chunk(data, NoMarks, writer)
+    }
+
+    #[inline]
+    fn process_marked<W: Write>(
+        &mut self,
+        data: &[u8],
+        marks: &[u8],
+        writer: &mut W,
+    ) -> io::Result<()> {
+        self.process_chunk(data, marks, writer)
+    }
+
+    /// Skip a hole with a single `-- hole start..end --` line.
+    fn hole<W: Write>(&mut self, len: u64, writer: &mut W) -> io::Result<()> {
+        self.skip("hole", len, writer)
+    }
+
+    /// End the current line early and mark the unreadable bytes with a
+    /// `-- unreadable start..end --` line; they are never shown as zeros.
+    fn unreadable<W: Write>(&mut self, len: u64, writer: &mut W) -> io::Result<()> {
+        self.skip("unreadable", len, writer)
+    }
+
+    /// End the current line early like a full one.
+    fn flush_line<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        if self.char_pos > 0 {
+            self.end_line(writer)?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn finish<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        // Close a partial line like a full one, so text ending in " |"
+        // cannot be mistaken for the separator when reading the dump back.
+        if self.char_pos > 0 {
+            self.line_buf.push(b' ');
+            push_separator(&mut self.line_buf, separator_color(&self.config), b"|");
+        }
+        if self.line_buf.len() > 0 {
+            writer.write_all(self.line_buf.as_slice())?;
+            self.line_buf.reset();
+        }
+        writeln!(writer)?;
+        Ok(())
+    }
+}
+
+/// Interpretation of a value in typed mode.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    Unsigned,
+    Signed,
+    Float,
+}
+
+/// Fixed-size value type such as `u32le` or `f64be`.
+#[derive(Clone, Copy)]
+pub struct ValueType {
+    pub kind: ValueKind,
+    /// Size in bytes (1, 2, 4 or 8).
+    pub size: usize,
+    pub endian: Endian,
+}
+
+impl ValueType {
+    /// Column width that fits every value of this type.
+    fn width(&self) -> usize {
+        match (self.kind, self.size) {
+            (ValueKind::Unsigned, 1) => 3,
+            (ValueKind::Signed, 1) => 4,
+            (ValueKind::Unsigned, 2) => 5,
+            (ValueKind::Signed, 2) => 6,
+            (ValueKind::Unsigned, 4) => 10,
+            (ValueKind::Signed, 4) => 11,
+            (ValueKind::Float, 4) => 16,
+            (ValueKind::Float, _) => 24,
+            _ => 20,
+        }
+    }
+}
+
+impl FromStr for ValueType {
+    type Err = String;
+
+    /// Parse `u8`..`u64`, `i8`..`i64`, `f32` or `f64`, with an optional
+    /// `le`/`be` suffix (native byte order if omitted).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid type: {} (expected e.g. u8, i16le, u32be, f64le)",
+                s
+            )
+        };
+
+        let (name, endian) = if let Some(name) = s.strip_suffix("le") {
+            (name, Endian::Little)
+        } else if let Some(name) = s.strip_suffix("be") {
+            (name, Endian::Big)
+        } else if cfg!(target_endian = "little") {
+            (s, Endian::Little)
+        } else {
+            (s, Endian::Big)
+        };
+
+        let kind = match name.as_bytes().first() {
+            Some(b'u') => ValueKind::Unsigned,
+            Some(b'i') => ValueKind::Signed,
+            Some(b'f') => ValueKind::Float,
+            _ => return Err(invalid()),
+        };
+        let size = match (&name[1..], kind) {
+            ("8", ValueKind::Unsigned | ValueKind::Signed) => 1,
+            ("16", ValueKind::Unsigned | ValueKind::Signed) => 2,
+            ("32", _) => 4,
+            ("64", _) => 8,
+            _ => return Err(invalid()),
+        };
+
+        Ok(ValueType { kind, size, endian })
+    }
+}
+
+/// Typed dumper: reads the input as an array of fixed-size values and
+/// prints them in aligned decimal columns, like `od -t`.
+pub struct TypedDumper {
+    config: DumpConfig,
+    value_type: ValueType,
+    line_buf: LineBuffer,
+    offset: u64,
+    /// Values per line.
+    per_line: usize,
+    line_pos: usize,
+    /// Bytes of a value that straddles two chunks, and the first mark among them.
+    pending: [u8; 8],
+    pending_len: usize,
+    pending_mark: u8,
+    started: bool,
+}
+
+impl TypedDumper {
+    pub fn new(config: DumpConfig, value_type: ValueType) -> Self {
+        Self {
+            offset: config.start_offset,
+            per_line: (config.bytes_per_line / value_type.size).max(1),
+            config,
+            value_type,
+            line_buf: LineBuffer::new(),
+            line_pos: 0,
+            pending: [0u8; 8],
+            pending_len: 0,
+            pending_mark: 0,
+            started: false,
+        }
+    }
+
+    #[inline(always)]
+    fn process_value<W: Write>(
+        &mut self,
+        bytes: &[u8],
+        mark: u8,
+        writer: &mut W,
+    ) -> io::Result<()> {
+        self.push_value(bytes, mark);
+        self.line_pos += 1;
+
+        if self.line_pos >= self.per_line {
+            self.end_line(writer)?;
+        }
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn end_line<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        push_separator(&mut self.line_buf, separator_color(&self.config), b"|");
+        self.line_buf.push(b'\n');
         writer.write_all(self.line_buf.as_slice())?;
         self.line_buf.reset();
+        self.offset = self
+            .offset
+            .wrapping_add((self.line_pos * self.value_type.size) as u64);
+        self.line_pos = 0;
+        self.push_offset();
+        Ok(())
+    }
+
+    /// Push a trailing partial value, zero-filled as od does.
+    fn push_partial(&mut self) {
+        if self.pending_len == 0 {
+            return;
+        }
+        let mut value = [0u8; 8];
+        value[..self.pending_len].copy_from_slice(&self.pending[..self.pending_len]);
+        self.pending_len = 0;
+        self.push_value(&value[..self.value_type.size], self.pending_mark);
+        self.pending_mark = 0;
+    }
+
+    /// End the current line early and write a marker line for `len` bytes
+    /// that are not printed. The next line starts after them.
+    fn skip<W: Write>(&mut self, what: &str, len: u64, writer: &mut W) -> io::Result<()> {
+        let used = (self.line_pos * self.value_type.size + self.pending_len) as u64;
+        if used > 0 {
+            self.push_partial();
+            self.line_buf.push(b'\n');
+            writer.write_all(self.line_buf.as_slice())?;
+        }
+        // A line without values is dropped along with its offset
+        self.line_buf.reset();
+
+        let start = self.offset.wrapping_add(used);
+        self.offset = start.wrapping_add(len);
+        self.line_pos = 0;
+        write_marker(&self.config, what, start, self.offset, writer)?;
+        self.started = true;
+        self.push_offset();
         Ok(())
     }
 
+    /// Decode one value and push it right-aligned in its column, in the
+    /// color of the first highlight rule that marked one of its bytes.
+    #[inline(always)]
+    fn push_value(&mut self, bytes: &[u8], mark: u8) {
+        let bits = match self.value_type.endian {
+            Endian::Little => bytes.iter().rev().fold(0u64, |acc, &b| acc << 8 | b as u64),
+            Endian::Big => bytes.iter().fold(0u64, |acc, &b| acc << 8 | b as u64),
+        };
+
+        const CELL_LEN: usize = 32;
+        let mut cell = [0u8; CELL_LEN];
+        let len = {
+            let mut cursor = &mut cell[..];
+            match self.value_type.kind {
+                ValueKind::Unsigned => write!(cursor, "{}", bits),
+                ValueKind::Signed => {
+                    // Sign-extend from the value's width
+                    let shift = 64 - self.value_type.size * 8;
+                    write!(cursor, "{}", ((bits << shift) as i64) >> shift)
+                }
+                ValueKind::Float if self.value_type.size == 4 => {
+                    write_float(&mut cursor, f32::from_bits(bits as u32))
+                }
+                ValueKind::Float => write_float(&mut cursor, f64::from_bits(bits)),
+            }
+            .expect("value fits in cell");
+            CELL_LEN - cursor.len()
+        };
+
+        for _ in len..self.value_type.width() {
+            self.line_buf.push(b' ');
+        }
+        let col: &[u8] = if mark != 0 {
+            marked_color(&self.config, 0, mark)
+        } else if self.config.color_enabled && bits == 0 {
+            self.config.palette.class(ByteClass::Null)
+        } else {
+            &[]
+        };
+        push_colored(&mut self.line_buf, col, &cell[..len]);
+        self.line_buf.push(b' ');
+    }
+
+    /// Start a line with its offset; it is written out with the line.
+    #[inline(always)]
+    fn push_offset(&mut self) {
+        let col = offset_color(&self.config);
+        self.line_buf.extend(col);
+        offset::push_offset(
+            &mut self.line_buf,
+            self.offset,
+            self.config.offset_format,
+            self.config.offset_width,
+        );
+        if !col.is_empty() {
+            self.line_buf.extend(color::RESET);
+        }
+        push_separator(&mut self.line_buf, separator_color(&self.config), b"|");
+        self.line_buf.push(b' ');
+    }
+}
+
+impl TypedDumper {
     #[inline(always)]
-    fn push_offset_hex(&mut self) {
-        let off = self.offset;
-        const HEX: &[u8] = b"0123456789abcdef";
-        self.line_buf.push(HEX[(off >> 24) & 0xF]);
-        self.line_buf.push(HEX[(off >> 20) & 0xF]);
-        self.line_buf.push(HEX[(off >> 16) & 0xF]);
-        self.line_buf.push(HEX[(off >> 12) & 0xF]);
-        self.line_buf.push(HEX[(off >> 8) & 0xF]);
-        self.line_buf.push(HEX[(off >> 4) & 0xF]);
-        self.line_buf.push(HEX[off & 0xF]);
+    fn process_chunk<M: Marks, W: Write>(
+        &mut self,
+        mut data: &[u8],
+        mut marks: M,
+        writer: &mut W,
+    ) -> io::Result<()> {
+        if !self.started {
+            self.started = true;
+            self.push_offset();
+        }
+
+        let size = self.value_type.size;
+
+        // Complete a value left over from the previous chunk
+        if self.pending_len > 0 {
+            let take = (size - self.pending_len).min(data.len());
+            self.pending[self.pending_len..self.pending_len + take].copy_from_slice(&data[..take]);
+            self.pending_len += take;
+            if self.pending_mark == 0 {
+                self.pending_mark = marks.slice(0, take).first();
+            }
+            marks = marks.slice(take, data.len());
+            data = &data[take..];
+
+            if self.pending_len < size {
+                return Ok(());
+            }
+            let value = self.pending;
+            let mark = self.pending_mark;
+            self.pending_len = 0;
+            self.pending_mark = 0;
+            self.process_value(&value[..size], mark, writer)?;
+        }
+
+        let mut values = data.chunks_exact(size);
+        for (i, value) in (&mut values).enumerate() {
+            let mark = marks.slice(i * size, (i + 1) * size).first();
+            self.process_value(value, mark, writer)?;
+        }
+
+        let rest = values.remainder();
+        self.pending[..rest.len()].copy_from_slice(rest);
+        self.pending_len = rest.len();
+        self.pending_mark = marks.slice(data.len() - rest.len(), data.len()).first();
+
+        Ok(())
+    }
+}
+
+impl Dumper for TypedDumper {
+    #[inline]
+    fn process<W: Write>(&mut self, data: &[u8], writer: &mut W) -> io::Result<()> {
+        self.process_chunk(data, NoMarks, writer)
+    }
+
+    #[inline]
+    fn process_marked<W: Write>(
+        &mut self,
+        data: &[u8],
+        marks: &[u8],
+        writer: &mut W,
+    ) -> io::Result<()> {
+        self.process_chunk(data, marks, writer)
+    }
+
+    /// Skip the whole lines of a hole with a single `-- hole start..end --`
+    /// line. Bytes before the first and after the last whole line are
+    /// printed as zeros so every line keeps its alignment.
+    fn hole<W: Write>(&mut self, len: u64, writer: &mut W) -> io::Result<()> {
+        let line_bytes = (self.per_line * self.value_type.size) as u64;
+        let used = (self.line_pos * self.value_type.size + self.pending_len) as u64;
+        let lead = match used {
+            0 => 0,
+            used => (line_bytes - used).min(len),
+        };
+        process_zeros(self, lead, writer)?;
+
+        let skipped = (len - lead) / line_bytes * line_bytes;
+        if skipped > 0 {
+            self.skip("hole", skipped, writer)?;
+        }
+
+        process_zeros(self, len - lead - skipped, writer)
+    }
+
+    /// End the current line early and mark the unreadable bytes with a
+    /// `-- unreadable start..end --` line; they are never shown as zeros.
+    fn unreadable<W: Write>(&mut self, len: u64, writer: &mut W) -> io::Result<()> {
+        self.skip("unreadable", len, writer)
+    }
+
+    /// End the current line early like a full one. Bytes of an incomplete
+    /// value wait for the rest of it on the next line.
+    fn flush_line<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        if self.line_pos > 0 {
+            self.end_line(writer)?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn finish<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.push_partial();
+
+        if self.line_buf.len() > 0 {
+            writer.write_all(self.line_buf.as_slice())?;
+            self.line_buf.reset();
+        }
+        writeln!(writer)?;
+        Ok(())
+    }
+}
+
+/// Write a float in plain notation when that stays short, scientific otherwise.
+fn write_float<W: Write, F: Display + std::fmt::LowerExp + Into<f64> + Copy>(
+    writer: &mut W,
+    value: F,
+) -> io::Result<()> {
+    let magnitude = value.into().abs();
+    if magnitude == 0.0 || (1e-4..1e15).contains(&magnitude) || !magnitude.is_finite() {
+        write!(writer, "{}", value)
+    } else {
+        write!(writer, "{:e}", value)
     }
 }
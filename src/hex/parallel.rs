@@ -0,0 +1,245 @@
+//! Parallel formatting: large chunks are split at line starts and the
+//! pieces are formatted on separate threads, then written in order.
+
+use super::{BinaryDumper, Dumper, HexDumper, TextDumper, TEXT_ESCAPE};
+use std::io::{self, Write};
+use std::panic;
+use std::thread;
+
+/// Smallest piece worth a thread of its own.
+const MIN_PIECE_SIZE: usize = 256 * 1024;
+
+/// Dumpers whose state at the start of a line follows from the input
+/// alone, so a piece starting there can be formatted without the pieces
+/// before it.
+pub trait ChunkDumper: Dumper + Send + Sized {
+    /// Positions in `data` where lines would start if it were processed
+    /// next, about `spacing` bytes apart, in ascending order.
+    fn line_starts(&self, data: &[u8], spacing: usize) -> Vec<usize>;
+
+    /// A dumper in the state this one would reach after processing
+    /// `skipped`, which ends at one of its `line_starts`.
+    fn resume(&self, skipped: &[u8]) -> Self;
+}
+
+/// Wrapper that formats large chunks on up to `threads` threads. Output is
+/// byte-identical to running the wrapped dumper alone.
+pub struct ParallelDumper<D> {
+    inner: D,
+    threads: usize,
+    /// Output of each piece, reused between chunks.
+    buffers: Vec<Vec<u8>>,
+}
+
+impl<D: ChunkDumper> ParallelDumper<D> {
+    pub fn new(inner: D, threads: usize) -> Self {
+        Self {
+            inner,
+            threads,
+            buffers: Vec::new(),
+        }
+    }
+}
+
+impl<D: ChunkDumper> Dumper for ParallelDumper<D> {
+    fn process<W: Write>(&mut self, data: &[u8], writer: &mut W) -> io::Result<()> {
+        let pieces = self.threads.min(data.len() / MIN_PIECE_SIZE);
+        let starts = if pieces > 1 {
+            self.inner.line_starts(data, data.len() / pieces)
+        } else {
+            Vec::new()
+        };
+        if starts.is_empty() {
+            return self.inner.process(data, writer);
+        }
+
+        // The wrapped dumper formats the first piece; the others start fresh
+        let mut resumed: Vec<D> = starts
+            .iter()
+            .map(|&start| self.inner.resume(&data[..start]))
+            .collect();
+        let bounds: Vec<(usize, usize)> = [0]
+            .into_iter()
+            .chain(starts.iter().copied())
+            .zip(starts.iter().copied().chain([data.len()]))
+            .collect();
+
+        self.buffers.resize_with(bounds.len(), Vec::new);
+        for buffer in &mut self.buffers {
+            buffer.clear();
+        }
+        let (first, rest) = self.buffers.split_first_mut().expect("at least two pieces");
+        let inner = &mut self.inner;
+        thread::scope(|scope| {
+            let handles: Vec<_> = resumed
+                .iter_mut()
+                .zip(rest)
+                .zip(&bounds[1..])
+                .map(|((dumper, buffer), &(start, end))| {
+                    scope.spawn(move || dumper.process(&data[start..end], buffer))
+                })
+                .collect();
+
+            let result = inner.process(&data[..bounds[0].1], first);
+            for handle in handles {
+                handle.join().unwrap_or_else(|e| panic::resume_unwind(e))?;
+            }
+            result
+        })?;
+
+        for buffer in &self.buffers {
+            writer.write_all(buffer)?;
+        }
+        // The last piece's dumper carries on where the chunk ends
+        self.inner = resumed.pop().expect("at least one resumed piece");
+        Ok(())
+    }
+
+    /// Highlighted chunks arrive a piece at a time anyway, so they are
+    /// formatted on this thread.
+    fn process_marked<W: Write>(
+        &mut self,
+        data: &[u8],
+        marks: &[u8],
+        writer: &mut W,
+    ) -> io::Result<()> {
+        self.inner.process_marked(data, marks, writer)
+    }
+
+    fn finish<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.inner.finish(writer)
+    }
+
+    fn hole<W: Write>(&mut self, len: u64, writer: &mut W) -> io::Result<()> {
+        self.inner.hole(len, writer)
+    }
+
+    fn unreadable<W: Write>(&mut self, len: u64, writer: &mut W) -> io::Result<()> {
+        self.inner.unreadable(len, writer)
+    }
+
+    fn flush_line<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.inner.flush_line(writer)
+    }
+}
+
+/// Positions `first + k * unit` below `len` and at least `min`, taking the
+/// first one at or after each multiple of `spacing`.
+fn grid_starts(first: usize, unit: usize, min: usize, len: usize, spacing: usize) -> Vec<usize> {
+    let mut starts = Vec::new();
+    let mut target = spacing.max(min);
+    while target < len {
+        let start = first + (target.saturating_sub(first)).div_ceil(unit) * unit;
+        if start >= len {
+            break;
+        }
+        if starts.last() != Some(&start) {
+            starts.push(start);
+        }
+        target = start + spacing;
+    }
+    starts
+}
+
+impl ChunkDumper for HexDumper {
+    fn line_starts(&self, data: &[u8], spacing: usize) -> Vec<usize> {
+        let bytes_per_line = self.config.bytes_per_line;
+        let used = (self.line_pos + self.pending.len()) % bytes_per_line;
+        let first = (bytes_per_line - used) % bytes_per_line;
+        // Squeezing compares with the two lines before a piece, so they
+        // must lie within `data`
+        grid_starts(
+            first,
+            bytes_per_line,
+            2 * bytes_per_line,
+            data.len(),
+            spacing,
+        )
+    }
+
+    fn resume(&self, skipped: &[u8]) -> Self {
+        let mut config = self.config;
+        config.start_offset = self
+            .offset
+            .wrapping_add((self.line_pos + self.pending.len() + skipped.len()) as u64);
+
+        let mut dumper = HexDumper::new(config);
+        dumper.started = true;
+        if config.squeeze {
+            let bytes_per_line = config.bytes_per_line;
+            let end = skipped.len();
+            let last = &skipped[end - bytes_per_line..];
+            let before = &skipped[end - 2 * bytes_per_line..end - bytes_per_line];
+            dumper.previous.extend_from_slice(last);
+            dumper.squeezing = last == before;
+        }
+        dumper
+    }
+}
+
+impl ChunkDumper for BinaryDumper {
+    fn line_starts(&self, data: &[u8], spacing: usize) -> Vec<usize> {
+        // A line starts on a byte boundary every lcm(8, bits) bits
+        let bits_per_line = self.config.bytes_per_line;
+        let Some(first) =
+            (0..bits_per_line).find(|k| (self.bit_pos + 8 * k).is_multiple_of(bits_per_line))
+        else {
+            return Vec::new();
+        };
+        let unit = (1..=bits_per_line)
+            .find(|k| (8 * k).is_multiple_of(bits_per_line))
+            .unwrap_or(bits_per_line);
+        grid_starts(first, unit, 1, data.len(), spacing)
+    }
+
+    fn resume(&self, skipped: &[u8]) -> Self {
+        let mut config = self.config;
+        config.start_offset = self
+            .config
+            .start_offset
+            .wrapping_add((self.bit_count + self.bit_pos as u64) / 8 + skipped.len() as u64);
+
+        let mut dumper = BinaryDumper::new(config);
+        dumper.started = true;
+        dumper.push_offset();
+        dumper
+    }
+}
+
+impl ChunkDumper for TextDumper {
+    fn line_starts(&self, data: &[u8], spacing: usize) -> Vec<usize> {
+        // Escapes make lines uneven, so follow the line wrapping. Only wraps
+        // after a byte are used: a fresh line there matches exactly.
+        let chars_per_line = self.config.bytes_per_line;
+        let mut starts = Vec::new();
+        let mut target = spacing;
+        let mut char_pos = self.char_pos;
+        for (i, &byte) in data.iter().enumerate() {
+            let len = TEXT_ESCAPE[byte as usize].1 as usize;
+            if char_pos + len > chars_per_line {
+                char_pos = 0;
+            }
+            char_pos += len;
+            if char_pos >= chars_per_line {
+                char_pos = 0;
+                if i + 1 >= target && i + 1 < data.len() {
+                    starts.push(i + 1);
+                    target = i + 1 + spacing;
+                }
+            }
+        }
+        starts
+    }
+
+    fn resume(&self, skipped: &[u8]) -> Self {
+        let mut config = self.config;
+        config.start_offset = self
+            .offset
+            .wrapping_add((self.line_bytes + skipped.len()) as u64);
+
+        let mut dumper = TextDumper::new(config);
+        dumper.started = true;
+        dumper.push_offset();
+        dumper
+    }
+}
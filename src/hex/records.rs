@@ -0,0 +1,363 @@
+//! Intel HEX and Motorola S-record output for device programmers.
+
+use super::{DumpConfig, Dumper, UPPER_HEX_TABLE};
+use crate::output::buffer::LineBuffer;
+use clap::ValueEnum;
+use std::io::{self, Write};
+
+/// Most data bytes one record can hold (the length field is a single byte).
+pub const MAX_RECORD_BYTES: usize = 255;
+
+/// Record-based firmware file format.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RecordFormat {
+    /// Intel HEX
+    Ihex,
+    /// Motorola S-record (S19/S28/S37)
+    Srec,
+}
+
+/// Address width of S-record data records.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SrecType {
+    /// 16-bit addresses (S1 records)
+    S19,
+    /// 24-bit addresses (S2 records)
+    S28,
+    /// 32-bit addresses (S3 records)
+    S37,
+}
+
+impl SrecType {
+    /// Smallest type that can address `max_address`.
+    pub fn for_address(max_address: u64) -> Self {
+        if max_address <= 0xFFFF {
+            SrecType::S19
+        } else if max_address <= 0xFF_FFFF {
+            SrecType::S28
+        } else {
+            SrecType::S37
+        }
+    }
+
+    #[inline(always)]
+    pub fn address_bytes(self) -> usize {
+        match self {
+            SrecType::S19 => 2,
+            SrecType::S28 => 3,
+            SrecType::S37 => 4,
+        }
+    }
+
+    /// Most data bytes a record of this type can hold.
+    pub fn max_data_bytes(self) -> usize {
+        MAX_RECORD_BYTES - self.address_bytes() - 1
+    }
+}
+
+/// Record being assembled: address of its first byte and its data.
+struct Record {
+    address: u64,
+    data: [u8; MAX_RECORD_BYTES],
+    len: usize,
+}
+
+impl Record {
+    const fn new() -> Self {
+        Self {
+            address: 0,
+            data: [0; MAX_RECORD_BYTES],
+            len: 0,
+        }
+    }
+}
+
+/// Append `byte` as two uppercase hex digits and add it to `checksum`.
+#[inline(always)]
+fn push_byte(buf: &mut LineBuffer, byte: u8, checksum: &mut u8) {
+    buf.extend(&UPPER_HEX_TABLE[byte as usize]);
+    *checksum = checksum.wrapping_add(byte);
+}
+
+/// Write one Intel HEX record: `:LLAAAATT<data>CC`.
+fn write_ihex_record<W: Write>(
+    buf: &mut LineBuffer,
+    kind: u8,
+    address: u16,
+    data: &[u8],
+    writer: &mut W,
+) -> io::Result<()> {
+    let mut checksum = 0u8;
+    buf.push(b':');
+    push_byte(buf, data.len() as u8, &mut checksum);
+    for byte in address.to_be_bytes() {
+        push_byte(buf, byte, &mut checksum);
+    }
+    push_byte(buf, kind, &mut checksum);
+    for &byte in data {
+        push_byte(buf, byte, &mut checksum);
+    }
+    // Two's complement: all bytes of the record sum to zero
+    push_byte(buf, checksum.wrapping_neg(), &mut 0);
+    buf.push(b'\n');
+
+    writer.write_all(buf.as_slice())?;
+    buf.reset();
+    Ok(())
+}
+
+/// Write one S-record: `S<kind>` count, address, data and checksum.
+fn write_srec_record<W: Write>(
+    buf: &mut LineBuffer,
+    kind: u8,
+    address: u64,
+    address_bytes: usize,
+    data: &[u8],
+    writer: &mut W,
+) -> io::Result<()> {
+    let mut checksum = 0u8;
+    buf.push(b'S');
+    buf.push(b'0' + kind);
+    push_byte(buf, (address_bytes + data.len() + 1) as u8, &mut checksum);
+    for &byte in &address.to_be_bytes()[8 - address_bytes..] {
+        push_byte(buf, byte, &mut checksum);
+    }
+    for &byte in data {
+        push_byte(buf, byte, &mut checksum);
+    }
+    // One's complement of the sum of count, address and data
+    push_byte(buf, !checksum, &mut 0);
+    buf.push(b'\n');
+
+    writer.write_all(buf.as_slice())?;
+    buf.reset();
+    Ok(())
+}
+
+fn address_error(address: u64, limit: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("address {:#x} does not fit in {}", address, limit),
+    )
+}
+
+/// Intel HEX dumper: data records of up to `bytes_per_line` bytes, with
+/// extended linear address records for addresses above 64 KiB.
+pub struct IntelHexDumper {
+    config: DumpConfig,
+    line_buf: LineBuffer,
+    record: Record,
+    /// Address of the next input byte.
+    address: u64,
+    /// Upper 16 address bits set by the last extended linear address record.
+    upper: u64,
+}
+
+impl IntelHexDumper {
+    pub fn new(config: DumpConfig) -> Self {
+        let address = config.start_offset;
+        Self {
+            config,
+            line_buf: LineBuffer::new(),
+            record: Record::new(),
+            address,
+            upper: 0,
+        }
+    }
+
+    fn flush_record<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        if self.record.len == 0 {
+            return Ok(());
+        }
+
+        let upper = self.record.address >> 16;
+        if upper > 0xFFFF {
+            return Err(address_error(self.record.address, "Intel HEX's 32 bits"));
+        }
+        if upper != self.upper {
+            let data = (upper as u16).to_be_bytes();
+            write_ihex_record(&mut self.line_buf, 0x04, 0, &data, writer)?;
+            self.upper = upper;
+        }
+
+        let data = &self.record.data[..self.record.len];
+        write_ihex_record(
+            &mut self.line_buf,
+            0x00,
+            self.record.address as u16,
+            data,
+            writer,
+        )?;
+        self.record.len = 0;
+        Ok(())
+    }
+}
+
+impl Dumper for IntelHexDumper {
+    #[inline]
+    fn process<W: Write>(&mut self, data: &[u8], writer: &mut W) -> io::Result<()> {
+        for &byte in data {
+            if self.record.len == 0 {
+                self.record.address = self.address;
+            }
+            self.record.data[self.record.len] = byte;
+            self.record.len += 1;
+            self.address = self.address.wrapping_add(1);
+
+            // Records may not cross a 64 KiB boundary
+            if self.record.len >= self.config.bytes_per_line || self.address & 0xFFFF == 0 {
+                self.flush_record(writer)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Leave a hole out of the records; the next record starts after it.
+    fn hole<W: Write>(&mut self, len: u64, writer: &mut W) -> io::Result<()> {
+        self.flush_record(writer)?;
+        self.address = self.address.wrapping_add(len);
+        Ok(())
+    }
+
+    /// Leave unreadable memory out of the records like a hole.
+    fn unreadable<W: Write>(&mut self, len: u64, writer: &mut W) -> io::Result<()> {
+        self.hole(len, writer)
+    }
+
+    #[inline]
+    fn finish<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.flush_record(writer)?;
+        write_ihex_record(&mut self.line_buf, 0x01, 0, &[], writer)
+    }
+}
+
+/// S-record dumper: a header, data records of up to `bytes_per_line` bytes,
+/// a record count and a termination record.
+pub struct SrecDumper {
+    config: DumpConfig,
+    srec_type: SrecType,
+    line_buf: LineBuffer,
+    record: Record,
+    /// Address of the next input byte.
+    address: u64,
+    records: u64,
+    started: bool,
+}
+
+impl SrecDumper {
+    pub fn new(config: DumpConfig, srec_type: SrecType) -> Self {
+        let address = config.start_offset;
+        Self {
+            config,
+            srec_type,
+            line_buf: LineBuffer::new(),
+            record: Record::new(),
+            address,
+            records: 0,
+            started: false,
+        }
+    }
+
+    /// Write an `S<kind>` record for `address` with no data.
+    fn write_empty<W: Write>(
+        &mut self,
+        kind: u8,
+        address: u64,
+        address_bytes: usize,
+        writer: &mut W,
+    ) -> io::Result<()> {
+        write_srec_record(
+            &mut self.line_buf,
+            kind,
+            address,
+            address_bytes,
+            &[],
+            writer,
+        )
+    }
+
+    fn flush_record<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        if self.record.len == 0 {
+            return Ok(());
+        }
+
+        let address_bytes = self.srec_type.address_bytes();
+        let last = self
+            .record
+            .address
+            .saturating_add(self.record.len as u64 - 1);
+        if last >> (address_bytes * 8) != 0 {
+            let limit = format!("{}-bit S-record addresses", address_bytes * 8);
+            return Err(address_error(last, &limit));
+        }
+
+        write_srec_record(
+            &mut self.line_buf,
+            address_bytes as u8 - 1,
+            self.record.address,
+            address_bytes,
+            &self.record.data[..self.record.len],
+            writer,
+        )?;
+        self.record.len = 0;
+        self.records += 1;
+        Ok(())
+    }
+}
+
+impl Dumper for SrecDumper {
+    #[inline]
+    fn process<W: Write>(&mut self, data: &[u8], writer: &mut W) -> io::Result<()> {
+        if !self.started {
+            // Header record with no module name
+            self.write_empty(0, 0, 2, writer)?;
+            self.started = true;
+        }
+
+        for &byte in data {
+            if self.record.len == 0 {
+                self.record.address = self.address;
+            }
+            self.record.data[self.record.len] = byte;
+            self.record.len += 1;
+            self.address = self.address.wrapping_add(1);
+
+            if self.record.len >= self.config.bytes_per_line {
+                self.flush_record(writer)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Leave a hole out of the records; the next record starts after it.
+    fn hole<W: Write>(&mut self, len: u64, writer: &mut W) -> io::Result<()> {
+        self.flush_record(writer)?;
+        self.address = self.address.wrapping_add(len);
+        Ok(())
+    }
+
+    /// Leave unreadable memory out of the records like a hole.
+    fn unreadable<W: Write>(&mut self, len: u64, writer: &mut W) -> io::Result<()> {
+        self.hole(len, writer)
+    }
+
+    #[inline]
+    fn finish<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        if !self.started {
+            self.write_empty(0, 0, 2, writer)?;
+            self.started = true;
+        }
+        self.flush_record(writer)?;
+
+        // S5/S6 record count, omitted if it does not fit in 24 bits
+        if self.records <= 0xFFFF {
+            self.write_empty(5, self.records, 2, writer)?;
+        } else if self.records <= 0xFF_FFFF {
+            self.write_empty(6, self.records, 3, writer)?;
+        }
+
+        // Termination record matching the data record type
+        let address_bytes = self.srec_type.address_bytes();
+        self.write_empty(11 - address_bytes as u8, 0, address_bytes, writer)
+    }
+}
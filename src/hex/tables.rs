@@ -16,6 +16,53 @@ pub const HEX_TABLE: [[u8; 3]; 256] = {
     table
 };
 
+/// Pre-computed byte-to-uppercase-hex table without separator, as used by
+/// Intel HEX and S-record files. Index with byte value to get "XX" as [u8; 2].
+pub const UPPER_HEX_TABLE: [[u8; 2]; 256] = {
+    let mut table = [[0u8; 2]; 256];
+    let hex_chars = b"0123456789ABCDEF";
+    let mut i = 0;
+    while i < 256 {
+        table[i][0] = hex_chars[i >> 4];
+        table[i][1] = hex_chars[i & 0x0F];
+        i += 1;
+    }
+    table
+};
+
+/// Pre-computed byte-to-octal conversion table with trailing space.
+/// Index with byte value to get "ooo " as [u8; 4].
+pub const OCTAL_TABLE: [[u8; 4]; 256] = {
+    let mut table = [[0u8; 4]; 256];
+    let mut i = 0;
+    while i < 256 {
+        table[i][0] = b'0' + (i >> 6) as u8;
+        table[i][1] = b'0' + ((i >> 3) & 0x07) as u8;
+        table[i][2] = b'0' + (i & 0x07) as u8;
+        table[i][3] = b' ';
+        i += 1;
+    }
+    table
+};
+
+/// Pre-computed byte-to-decimal conversion table with trailing space.
+/// Index with byte value to get "ddd " as [u8; 4], right-aligned with spaces.
+pub const DECIMAL_TABLE: [[u8; 4]; 256] = {
+    let mut table = [[b' '; 4]; 256];
+    let mut i = 0;
+    while i < 256 {
+        table[i][2] = b'0' + (i % 10) as u8;
+        if i >= 10 {
+            table[i][1] = b'0' + (i / 10 % 10) as u8;
+        }
+        if i >= 100 {
+            table[i][0] = b'0' + (i / 100) as u8;
+        }
+        i += 1;
+    }
+    table
+};
+
 /// Pre-computed byte-to-binary conversion table.
 /// Each byte maps to 8 ASCII '0'/'1' characters.
 pub const BINARY_TABLE: [[u8; 8]; 256] = {
@@ -67,6 +114,57 @@ pub const BYTE_CLASS: [ByteClass; 256] = {
     table
 };
 
+/// Stops of the viridis color map, a perceptually uniform gradient from
+/// dark purple through teal to yellow.
+const VIRIDIS: [(u8, u8, u8); 9] = [
+    (68, 1, 84),
+    (71, 44, 122),
+    (59, 81, 139),
+    (44, 113, 142),
+    (33, 144, 141),
+    (39, 173, 129),
+    (92, 200, 99),
+    (170, 220, 50),
+    (253, 231, 37),
+];
+
+/// Color of every byte value for gradient coloring: 0x00 is dark purple,
+/// 0xff bright yellow, and neighbouring values get neighbouring colors.
+/// Index with byte value to get (r, g, b).
+pub const BYTE_GRADIENT: [(u8, u8, u8); 256] = {
+    let mut table = [(0u8, 0u8, 0u8); 256];
+    let segments = VIRIDIS.len() - 1;
+    let mut i = 0;
+    while i < 256 {
+        // Position along the stops in 1/255ths of a segment
+        let position = i * segments;
+        let stop = if position / 255 < segments {
+            position / 255
+        } else {
+            segments - 1
+        };
+        let t = (position - stop * 255) as i32;
+        let (r0, g0, b0) = VIRIDIS[stop];
+        let (r1, g1, b1) = VIRIDIS[stop + 1];
+        let mut color = (
+            (r0 as i32 + (r1 as i32 - r0 as i32) * t / 255) as u8,
+            (g0 as i32 + (g1 as i32 - g0 as i32) * t / 255) as u8,
+            (b0 as i32 + (b1 as i32 - b0 as i32) * t / 255) as u8,
+        );
+        // Where the map changes slowly, rounding can repeat the previous
+        // color; green only rises along the map, so nudge it up
+        if i > 0 {
+            let previous = table[i - 1];
+            if color.0 == previous.0 && color.1 == previous.1 && color.2 == previous.2 {
+                color.1 += 1;
+            }
+        }
+        table[i] = color;
+        i += 1;
+    }
+    table
+};
+
 /// Pre-computed escape sequences for text mode.
 /// Returns the escaped form and its length.
 pub const TEXT_ESCAPE: [([u8; 4], u8); 256] = {
@@ -75,6 +173,12 @@ pub const TEXT_ESCAPE: [([u8; 4], u8); 256] = {
     let mut i = 0;
     while i < 256 {
         match i as u8 {
+            // Backslash is escaped so escapes in the output stay unambiguous
+            b'\\' => {
+                table[i].0[0] = b'\\';
+                table[i].0[1] = b'\\';
+                table[i].1 = 2;
+            }
             // Printable ASCII (0x20-0x7E) - direct output
             0x20..=0x7E => {
                 table[i].0[0] = i as u8;
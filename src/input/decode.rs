@@ -0,0 +1,195 @@
+//! Streaming decoders for base64, base32 and ascii85 input.
+
+use crate::hex::{Encoding, ASCII85_BASE};
+use std::io::{self, BufRead, Read};
+
+/// Reader that decodes text from an inner reader, ignoring whitespace.
+pub struct Decoder<R> {
+    reader: R,
+    state: DecodeState,
+    /// Decoded bytes not yet returned to the caller.
+    out: Vec<u8>,
+    out_pos: usize,
+    done: bool,
+}
+
+impl<R: BufRead> Decoder<R> {
+    pub fn new(reader: R, encoding: Encoding) -> Self {
+        Self {
+            reader,
+            state: DecodeState {
+                encoding,
+                acc: 0,
+                bits: 0,
+                group: [0; 5],
+                group_len: 0,
+                position: 0,
+                started: false,
+                open_bracket: false,
+                ended: false,
+            },
+            out: Vec::new(),
+            out_pos: 0,
+            done: false,
+        }
+    }
+}
+
+impl<R: BufRead> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.out_pos < self.out.len() {
+                let n = buf.len().min(self.out.len() - self.out_pos);
+                buf[..n].copy_from_slice(&self.out[self.out_pos..self.out_pos + n]);
+                self.out_pos += n;
+                return Ok(n);
+            }
+            if self.done {
+                return Ok(0);
+            }
+
+            self.out.clear();
+            self.out_pos = 0;
+
+            let data = self.reader.fill_buf()?;
+            if data.is_empty() {
+                self.state.finish(&mut self.out)?;
+                self.done = true;
+                continue;
+            }
+            let len = data.len();
+            for &c in data {
+                self.state.feed(c, &mut self.out)?;
+            }
+            self.reader.consume(len);
+        }
+    }
+}
+
+/// Decoding state carried between reads.
+struct DecodeState {
+    encoding: Encoding,
+    /// Pending bits of a base64/base32 byte.
+    acc: u32,
+    bits: u32,
+    /// Digits of an incomplete ascii85 group.
+    group: [u8; 5],
+    group_len: usize,
+    /// Offset of the current input character, for error messages.
+    position: u64,
+    /// A non-whitespace character has been seen.
+    started: bool,
+    /// A leading '<' that may open an ascii85 "<~" delimiter.
+    open_bracket: bool,
+    /// The ascii85 "~>" delimiter has been reached; the rest is ignored.
+    ended: bool,
+}
+
+impl DecodeState {
+    #[inline(always)]
+    fn feed(&mut self, c: u8, out: &mut Vec<u8>) -> io::Result<()> {
+        self.position += 1;
+        if c.is_ascii_whitespace() || self.ended {
+            return Ok(());
+        }
+
+        let first = !self.started;
+        self.started = true;
+
+        match self.encoding {
+            Encoding::Base64 | Encoding::Base32 => {
+                if c == b'=' {
+                    // Padding ends the group; leftover bits are zero fill
+                    self.acc = 0;
+                    self.bits = 0;
+                    return Ok(());
+                }
+                let value = self.digit(c)?;
+                let width = self.encoding.bits_per_char();
+                self.acc = self.acc << width | value as u32;
+                self.bits += width;
+                if self.bits >= 8 {
+                    self.bits -= 8;
+                    out.push((self.acc >> self.bits) as u8);
+                }
+            }
+            Encoding::Ascii85 => {
+                if first && c == b'<' {
+                    self.open_bracket = true;
+                    return Ok(());
+                }
+                if std::mem::take(&mut self.open_bracket) {
+                    if c == b'~' {
+                        return Ok(());
+                    }
+                    // Not a delimiter after all: '<' was a digit
+                    self.push_ascii85(b'<', out)?;
+                }
+                match c {
+                    b'~' => {
+                        self.ended = true;
+                        return Ok(());
+                    }
+                    b'z' if self.group_len == 0 => out.extend_from_slice(&[0; 4]),
+                    _ => self.push_ascii85(c, out)?,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn push_ascii85(&mut self, c: u8, out: &mut Vec<u8>) -> io::Result<()> {
+        self.group[self.group_len] = self.digit(c)?;
+        self.group_len += 1;
+        if self.group_len == 5 {
+            let value = self.ascii85_value()?;
+            out.extend_from_slice(&value.to_be_bytes());
+            self.group_len = 0;
+        }
+        Ok(())
+    }
+
+    /// Flush a final partial group at the end of the input.
+    fn finish(&mut self, out: &mut Vec<u8>) -> io::Result<()> {
+        if std::mem::take(&mut self.open_bracket) {
+            self.push_ascii85(b'<', out)?;
+        }
+        if self.encoding != Encoding::Ascii85 || self.group_len == 0 {
+            return Ok(());
+        }
+        if self.group_len == 1 {
+            return Err(self.error("truncated ascii85 group"));
+        }
+
+        // A group of n digits is padded with 'u' and decodes to n - 1 bytes
+        let n = self.group_len;
+        self.group[n..].fill(b'u' - ASCII85_BASE);
+        let value = self.ascii85_value()?;
+        out.extend_from_slice(&value.to_be_bytes()[..n - 1]);
+        self.group_len = 0;
+        Ok(())
+    }
+
+    fn ascii85_value(&self) -> io::Result<u32> {
+        let value = self
+            .group
+            .iter()
+            .fold(0u64, |acc, &digit| acc * 85 + digit as u64);
+        u32::try_from(value).map_err(|_| self.error("ascii85 group out of range"))
+    }
+
+    #[inline(always)]
+    fn digit(&self, c: u8) -> io::Result<u8> {
+        self.encoding
+            .digit_value(c)
+            .ok_or_else(|| self.error(&format!("invalid character {:?}", c as char)))
+    }
+
+    fn error(&self, msg: &str) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("decoding input at offset {}: {}", self.position - 1, msg),
+        )
+    }
+}
@@ -0,0 +1,87 @@
+//! Transparent decompression of gzip, zlib, zstd and xz inputs.
+
+use clap::ValueEnum;
+use std::io::{self, Read};
+
+/// Longest magic number checked by `Compression::detect`.
+pub const MAGIC_LEN: usize = 6;
+
+/// How to decompress the input, selected with `--decompress`.
+#[derive(Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Decompress {
+    /// Detect gzip, zstd and xz from their magic bytes
+    #[default]
+    Auto,
+    /// Dump the raw bytes
+    None,
+    Gzip,
+    Zlib,
+    Zstd,
+    Xz,
+}
+
+impl Decompress {
+    /// Compression to undo, given the first bytes of the input.
+    pub fn resolve(self, head: &[u8]) -> Option<Compression> {
+        match self {
+            Decompress::Auto => Compression::detect(head),
+            Decompress::None => None,
+            Decompress::Gzip => Some(Compression::Gzip),
+            Decompress::Zlib => Some(Compression::Zlib),
+            Decompress::Zstd => Some(Compression::Zstd),
+            Decompress::Xz => Some(Compression::Xz),
+        }
+    }
+}
+
+/// Compression format of an input.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zlib,
+    Zstd,
+    Xz,
+}
+
+impl Compression {
+    /// Detect a format from its magic number. zlib has only a two-byte
+    /// header that plain data often matches, so it is never detected.
+    pub fn detect(head: &[u8]) -> Option<Self> {
+        if head.starts_with(&[0x1f, 0x8b]) {
+            Some(Compression::Gzip)
+        } else if head.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Compression::Zstd)
+        } else if head.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Compression::Xz)
+        } else {
+            None
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Zlib => "zlib",
+            Compression::Zstd => "zstd",
+            Compression::Xz => "xz",
+        }
+    }
+
+    /// Wrap `reader` in a decoder for this format. Concatenated gzip
+    /// members, zstd frames and xz streams are all decoded.
+    pub fn decoder<R: Read + Send + 'static>(self, reader: R) -> io::Result<Box<dyn Read + Send>> {
+        Ok(match self {
+            Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+            Compression::Zlib => Box::new(flate2::read::ZlibDecoder::new(reader)),
+            Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(reader)?),
+            Compression::Xz => Box::new(liblzma::read::XzDecoder::new_multi_decoder(reader)),
+        })
+    }
+}
+
+/// Read up to `MAGIC_LEN` bytes, stopping early only at end of input.
+pub fn read_head<R: Read>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let mut head = Vec::with_capacity(MAGIC_LEN);
+    reader.take(MAGIC_LEN as u64).read_to_end(&mut head)?;
+    Ok(head)
+}
@@ -0,0 +1,117 @@
+//! Following a file that is being appended to, like `tail -f`.
+
+use super::ByteRange;
+use std::fs::{self, File, Metadata};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::thread;
+use std::time::Duration;
+
+/// How often a file at its end is checked for new bytes.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// What `Follower::next` found.
+pub enum FollowEvent {
+    /// This many new bytes were read into the buffer.
+    Data(usize),
+    /// The file shrank below the read position; reading restarts at its start.
+    Truncated,
+    /// The path now names a different file, which is read from its start.
+    Rotated,
+}
+
+/// Reader that waits at the end of a file for more bytes instead of
+/// stopping, and notices when the file is truncated or replaced.
+pub struct Follower {
+    path: String,
+    file: File,
+    /// File position of the next byte to read.
+    position: u64,
+}
+
+impl Follower {
+    /// Open `path` and start at the beginning of `range` within its current contents.
+    pub fn open(path: &str, range: &ByteRange) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let metadata = file.metadata()?;
+        if !metadata.is_file() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "follow mode needs a regular file",
+            ));
+        }
+
+        let (position, _) = range.resolve(metadata.len());
+        file.seek(SeekFrom::Start(position))?;
+        Ok(Self {
+            path: path.to_string(),
+            file,
+            position,
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// File position of the next byte to read.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Read the next bytes, waiting for the file to grow if needed.
+    /// `idle` is called before every wait, so output can be flushed.
+    pub fn next<F>(&mut self, buf: &mut [u8], mut idle: F) -> io::Result<FollowEvent>
+    where
+        F: FnMut() -> io::Result<()>,
+    {
+        loop {
+            let n = self.file.read(buf)?;
+            if n > 0 {
+                self.position += n as u64;
+                return Ok(FollowEvent::Data(n));
+            }
+
+            // At the end: the old file has been read completely, so a
+            // replacement can be picked up without losing bytes
+            if let Ok(metadata) = fs::metadata(&self.path) {
+                if !same_file(&metadata, &self.file.metadata()?) {
+                    self.file = File::open(&self.path)?;
+                    self.position = 0;
+                    return Ok(FollowEvent::Rotated);
+                }
+            }
+            if self.file.metadata()?.len() < self.position {
+                self.file.seek(SeekFrom::Start(0))?;
+                self.position = 0;
+                return Ok(FollowEvent::Truncated);
+            }
+
+            idle()?;
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+impl Read for Follower {
+    /// Read without ever reaching the end; truncation and rotation just
+    /// continue with the new contents.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if let FollowEvent::Data(n) = self.next(buf, || Ok(()))? {
+                return Ok(n);
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn same_file(a: &Metadata, b: &Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+/// Without inode numbers, rotation cannot be told apart from the same file.
+#[cfg(not(unix))]
+fn same_file(_: &Metadata, _: &Metadata) -> bool {
+    true
+}
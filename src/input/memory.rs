@@ -0,0 +1,288 @@
+//! Live process memory through `/proc/<pid>/maps` and `/proc/<pid>/mem`.
+
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// Granularity of unreadable runs. Larger pages are probed in several steps.
+const PAGE_SIZE: u64 = 4096;
+
+/// One line of `/proc/<pid>/maps`.
+pub struct Mapping {
+    pub start: u64,
+    pub end: u64,
+    /// Permissions such as `r-xp`.
+    pub perms: String,
+    /// Mapped file path, `[heap]`-style pseudo name, or empty for anonymous memory.
+    pub name: String,
+}
+
+impl Mapping {
+    /// Last component of the mapped path.
+    fn file_name(&self) -> &str {
+        self.name.rsplit('/').next().unwrap_or("")
+    }
+}
+
+/// List the mappings of process `pid`.
+pub fn maps(pid: u32) -> io::Result<Vec<Mapping>> {
+    let text = fs::read_to_string(format!("/proc/{}/maps", pid))
+        .map_err(|e| io::Error::new(e.kind(), format!("process {}: {}", pid, e)))?;
+    text.lines()
+        .map(|line| {
+            parse_mapping(line).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unexpected line in /proc/{}/maps: {}", pid, line),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Parse `start-end perms offset dev inode [name]`.
+fn parse_mapping(line: &str) -> Option<Mapping> {
+    let mut fields = line.splitn(6, ' ');
+    let (start, end) = fields.next()?.split_once('-')?;
+    let perms = fields.next()?.to_string();
+    // Skip offset, device and inode; the name is padded with spaces
+    let name = fields.nth(3).unwrap_or("").trim_start().to_string();
+    Some(Mapping {
+        start: u64::from_str_radix(start, 16).ok()?,
+        end: u64::from_str_radix(end, 16).ok()?,
+        perms,
+        name,
+    })
+}
+
+/// Memory selected with `--region`.
+#[derive(Clone)]
+pub enum Region {
+    /// Mappings named like `heap` (`[heap]`) or mapping one file whose
+    /// name is this or starts with it up to a `-`, `.` or version number.
+    Name(String),
+    /// An address range `start..end`.
+    Range(u64, u64),
+}
+
+impl Region {
+    /// The mappings to dump. A range is returned as one entry named after
+    /// the mappings it overlaps, even if it spans unmapped addresses. A name
+    /// that only starts the names of several files is ambiguous.
+    pub fn select(&self, maps: &[Mapping]) -> io::Result<Vec<Mapping>> {
+        match self {
+            Region::Name(name) => {
+                let bracketed = format!("[{}]", name);
+                let exact: Vec<&Mapping> = maps
+                    .iter()
+                    .filter(|mapping| {
+                        mapping.name == *name
+                            || mapping.name == bracketed
+                            || mapping.file_name() == name
+                    })
+                    .collect();
+                let selected = if exact.is_empty() {
+                    let prefixed: Vec<&Mapping> = maps
+                        .iter()
+                        .filter(|mapping| {
+                            mapping
+                                .file_name()
+                                .strip_prefix(name.as_str())
+                                .and_then(|rest| rest.chars().next())
+                                .is_some_and(|c| c == '-' || c == '.' || c.is_ascii_digit())
+                        })
+                        .collect();
+                    let mut files: Vec<&str> = Vec::new();
+                    for mapping in &prefixed {
+                        if !files.contains(&&*mapping.name) {
+                            files.push(&mapping.name);
+                        }
+                    }
+                    if files.len() > 1 {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!(
+                                "region {} matches several files: {}",
+                                name,
+                                files.join(", ")
+                            ),
+                        ));
+                    }
+                    prefixed
+                } else {
+                    exact
+                };
+                Ok(selected
+                    .into_iter()
+                    .map(|mapping| Mapping {
+                        perms: mapping.perms.clone(),
+                        name: mapping.name.clone(),
+                        ..*mapping
+                    })
+                    .collect())
+            }
+            &Region::Range(start, end) => {
+                let mut names: Vec<&str> = Vec::new();
+                for mapping in maps {
+                    if mapping.start < end
+                        && start < mapping.end
+                        && !names.contains(&&*mapping.name)
+                    {
+                        names.push(&mapping.name);
+                    }
+                }
+                let names: Vec<&str> = names
+                    .into_iter()
+                    .map(|name| if name.is_empty() { "[anon]" } else { name })
+                    .collect();
+                Ok(vec![Mapping {
+                    start,
+                    end,
+                    perms: String::new(),
+                    name: names.join(", "),
+                }])
+            }
+        }
+    }
+}
+
+/// Parse a `--region`: a mapping name, or hex addresses `start-end` (as
+/// printed in `/proc/<pid>/maps`) or `start..end`, with or without `0x`.
+pub fn parse_region(s: &str) -> Result<Region, String> {
+    let range = s.split_once("..").or_else(|| s.split_once('-'));
+    let address = |text: &str| {
+        let text = text.trim();
+        let digits = text
+            .strip_prefix("0x")
+            .or_else(|| text.strip_prefix("0X"))
+            .unwrap_or(text);
+        u64::from_str_radix(digits, 16).ok()
+    };
+
+    match range.map(|(start, end)| (address(start), address(end))) {
+        Some((Some(start), Some(end))) if start < end => Ok(Region::Range(start, end)),
+        Some((Some(_), Some(_))) => Err(format!("empty address range: {}", s)),
+        _ if s.is_empty() => Err("empty region".to_string()),
+        _ => Ok(Region::Name(s.to_string())),
+    }
+}
+
+/// What `MemoryReader::next` found.
+pub enum MemoryChunk {
+    /// This many bytes were read into the buffer.
+    Data(usize),
+    /// This many bytes could not be read and were skipped.
+    Unreadable(u64),
+}
+
+/// Reader over `start..end` of a process's address space. Pages that cannot
+/// be read (guard pages, unmapped gaps) are reported instead of failing.
+pub struct MemoryReader {
+    file: File,
+    /// Address of the next byte to read.
+    position: u64,
+    end: u64,
+}
+
+impl MemoryReader {
+    pub fn open(pid: u32, start: u64, end: u64) -> io::Result<Self> {
+        Ok(Self {
+            file: File::open(format!("/proc/{}/mem", pid))?,
+            position: start,
+            end,
+        })
+    }
+
+    /// Read the next bytes, or measure the unreadable run at the current address.
+    pub fn next(&mut self, buf: &mut [u8]) -> io::Result<Option<MemoryChunk>> {
+        if self.position >= self.end {
+            return Ok(None);
+        }
+
+        let want = (self.end - self.position).min(buf.len() as u64) as usize;
+        match self.read_at(&mut buf[..want]) {
+            Ok(n) if n > 0 => {
+                self.position += n as u64;
+                return Ok(Some(MemoryChunk::Data(n)));
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => return self.next(buf),
+            _ => {}
+        }
+
+        // A read stops short of an unreadable page, so the current page is
+        // unreadable; probe page by page until memory is readable again
+        let start = self.position;
+        while self.position < self.end {
+            let page_end = ((self.position / PAGE_SIZE + 1) * PAGE_SIZE).min(self.end);
+            let len = ((page_end - self.position) as usize).min(buf.len());
+            match self.read_at(&mut buf[..len]) {
+                Ok(n) if n > 0 => break,
+                _ => self.position = page_end,
+            }
+        }
+        Ok(Some(MemoryChunk::Unreadable(self.position - start)))
+    }
+
+    /// Read at the current address without moving it.
+    fn read_at(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.seek(SeekFrom::Start(self.position))?;
+        self.file.read(buf)
+    }
+}
+
+impl Read for MemoryReader {
+    /// Read, failing with the address range of unreadable memory. The
+    /// reader has moved past it, so the next read carries on after it.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.next(buf)? {
+            None => Ok(0),
+            Some(MemoryChunk::Data(n)) => Ok(n),
+            Some(MemoryChunk::Unreadable(len)) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "memory {:#x}..{:#x} cannot be read",
+                    self.position - len,
+                    self.position
+                ),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(region: &str, maps: &[Mapping]) -> io::Result<Vec<String>> {
+        let region = parse_region(region).unwrap();
+        let selected = region.select(maps)?;
+        Ok(selected.into_iter().map(|mapping| mapping.name).collect())
+    }
+
+    #[test]
+    fn library_names_stop_at_a_boundary() {
+        let maps: Vec<Mapping> = [
+            "1000-2000 r--p 00000000 08:01 11 /usr/lib/libcrypto.so.3",
+            "2000-3000 r--p 00000000 08:01 12 /usr/lib/libc.so.6",
+            "3000-4000 r-xp 00001000 08:01 12 /usr/lib/libc.so.6",
+            "4000-5000 rw-p 00000000 00:00 0 [heap]",
+            "5000-6000 r--p 00000000 08:01 13 /usr/lib/libssl.so.3",
+            "6000-7000 r--p 00000000 08:01 14 /usr/lib/libssl-extra.so",
+        ]
+        .iter()
+        .map(|line| parse_mapping(line).unwrap())
+        .collect();
+
+        assert_eq!(
+            names("libc", &maps).unwrap(),
+            ["/usr/lib/libc.so.6", "/usr/lib/libc.so.6"]
+        );
+        assert_eq!(names("heap", &maps).unwrap(), ["[heap]"]);
+        assert_eq!(
+            names("libssl.so.3", &maps).unwrap(),
+            ["/usr/lib/libssl.so.3"]
+        );
+        assert!(names("libcrypt", &maps).unwrap().is_empty());
+        let error = names("libssl", &maps).unwrap_err();
+        assert!(error.to_string().contains("several files"), "{}", error);
+    }
+}
@@ -1,41 +1,179 @@
 //! Memory-mapped file reader for zero-copy I/O.
 
+use super::{sigbus, sparse, READ_BUFFER_SIZE};
 use memmap2::Mmap;
 use std::fs::File;
-use std::io;
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// Bytes handed out between checks that the file has not shrunk; large
+/// enough to be split across threads.
+const CHUNK_SIZE: usize = 8 * 1024 * 1024;
 
 /// Memory-mapped file reader.
 pub struct MmapReader {
     mmap: Mmap,
+    /// The mapped file, to notice it shrinking and read what is left.
+    file: File,
+    start: usize,
+    end: usize,
+    /// Holes of a sparse file within the window, as `(start, end)` offsets.
+    holes: Vec<(usize, usize)>,
+    /// Handle of the SIGBUS protection of the mapping.
+    guard: Option<usize>,
+}
+
+/// Part of the window: mapped bytes, or the length of a hole that reads as zeros.
+pub enum Segment<'a> {
+    Data(&'a [u8]),
+    Hole(u64),
 }
 
 impl MmapReader {
     /// Create a new memory-mapped reader from a file.
     pub fn new(file: &File) -> io::Result<Self> {
-        // SAFETY: File is opened read-only, no concurrent modifications expected.
+        // SAFETY: File is opened read-only. If another process truncates it,
+        // reads past the new end fault; `sigbus` turns that into an error.
         let mmap = unsafe { Mmap::map(file)? };
+        let guard = sigbus::register(mmap.as_ptr() as usize, mmap.len());
 
         // Advise kernel about sequential access pattern for better prefetching.
         #[cfg(unix)]
         mmap.advise(memmap2::Advice::Sequential).ok();
 
-        Ok(Self { mmap })
+        let end = mmap.len();
+        Ok(Self {
+            mmap,
+            file: file.try_clone()?,
+            start: 0,
+            end,
+            holes: Vec::new(),
+            guard,
+        })
+    }
+
+    /// Narrow the visible window to `start..end` of the mapping.
+    pub fn restrict(&mut self, start: usize, end: usize) {
+        self.start = start;
+        self.end = end;
+    }
+
+    /// Look up the holes of `file` within the window, so `segments` can skip
+    /// them without faulting in pages of zeros.
+    pub fn find_holes(&mut self, file: &File) {
+        self.holes = sparse::holes(file, self.start as u64, self.end as u64)
+            .into_iter()
+            .map(|(start, end)| (start as usize, end as usize))
+            .collect();
+    }
+
+    /// Pass the window to `process` as data and holes, in file order, a
+    /// chunk at a time. If the file shrinks, what is left of it is read
+    /// with plain reads instead, and an error names where it ended.
+    pub fn for_each_segment<F>(&self, mut process: F) -> io::Result<()>
+    where
+        F: FnMut(Segment<'_>) -> io::Result<()>,
+    {
+        let mut position = self.start;
+        let holes = self.holes.iter().copied().chain([(self.end, self.end)]);
+        for (hole_start, hole_end) in holes {
+            while position < hole_start {
+                let chunk_end = (position + CHUNK_SIZE).min(hole_start);
+                let len = self.file.metadata()?.len();
+                if len < chunk_end as u64 {
+                    return self.read_rest(position, len, &mut process);
+                }
+
+                process(Segment::Data(&self.mmap[position..chunk_end]))?;
+                self.check()?;
+                position = chunk_end;
+            }
+            if hole_end > hole_start {
+                process(Segment::Hole((hole_end - hole_start) as u64))?;
+                position = hole_end;
+            }
+        }
+        Ok(())
+    }
+
+    /// Read the window from `position` on after the file shrank to `len` bytes.
+    fn read_rest<F>(&self, position: usize, len: u64, process: &mut F) -> io::Result<()>
+    where
+        F: FnMut(Segment<'_>) -> io::Result<()>,
+    {
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(position as u64))?;
+        let mut reader = file.take((self.end - position) as u64);
+        let mut buffer = vec![0u8; READ_BUFFER_SIZE];
+        let mut position = position;
+        loop {
+            let n = reader.read(&mut buffer)?;
+            if n == 0 {
+                break;
+            }
+            process(Segment::Data(&buffer[..n]))?;
+            position += n;
+        }
+
+        if position < self.end {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!(
+                    "file was truncated to {} bytes while being read; the dump ends at offset {:#x}",
+                    len, position
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Fail if the file shrank under pages that were read, so they read as zeros.
+    pub fn check(&self) -> io::Result<()> {
+        let Some(page) = self.guard.and_then(sigbus::fault) else {
+            return Ok(());
+        };
+        let offset = page - self.mmap.as_ptr() as usize;
+        Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!(
+                "file was truncated while being read; bytes from offset {:#x} read as zeros",
+                offset
+            ),
+        ))
     }
 
     #[inline(always)]
     pub fn as_slice(&self) -> &[u8] {
-        &self.mmap[..]
+        &self.mmap[self.start..self.end]
     }
 
     #[inline(always)]
     #[allow(dead_code)]
     pub fn len(&self) -> usize {
-        self.mmap.len()
+        self.end - self.start
     }
 
     #[inline(always)]
     #[allow(dead_code)]
     pub fn is_empty(&self) -> bool {
-        self.mmap.is_empty()
+        self.start == self.end
+    }
+}
+
+impl Read for MmapReader {
+    /// Copy from the start of the window, consuming what was read.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.end - self.start);
+        buf[..n].copy_from_slice(&self.mmap[self.start..self.start + n]);
+        self.check()?;
+        self.start += n;
+        Ok(n)
+    }
+}
+
+impl Drop for MmapReader {
+    fn drop(&mut self) {
+        if let Some(guard) = self.guard {
+            sigbus::unregister(guard);
+        }
     }
 }
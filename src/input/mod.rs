@@ -1,11 +1,28 @@
 //! I/O abstractions for efficient file and stream reading.
 
+mod decode;
+mod decompress;
+mod follow;
+mod memory;
 mod mmap;
+mod range;
+mod records;
+mod sigbus;
+mod sparse;
 
-pub use mmap::MmapReader;
+pub use decode::Decoder;
+pub use decompress::{Compression, Decompress};
+pub use follow::{FollowEvent, Follower};
+pub use memory::{maps, parse_region, Mapping, MemoryChunk, MemoryReader, Region};
+pub use mmap::{MmapReader, Segment};
+pub use range::{parse_size, ByteRange, Tail};
+pub use records::Image;
 
+use crate::hex::{Encoding, RecordFormat};
+use decompress::read_head;
+use std::collections::VecDeque;
 use std::fs::File;
-use std::io::{self, BufReader, Read};
+use std::io::{self, BufReader, Cursor, Read, Seek, SeekFrom};
 
 /// Minimum file size to use memory mapping (below this, regular read is faster).
 pub const MMAP_THRESHOLD: u64 = 64 * 1024;
@@ -16,35 +33,334 @@ pub const READ_BUFFER_SIZE: usize = 256 * 1024;
 /// Write buffer size for output.
 pub const WRITE_BUFFER_SIZE: usize = 64 * 1024;
 
-/// Input source abstraction - either memory-mapped or streaming.
+/// Input source abstraction - memory-mapped, streaming, streaming
+/// through a decompressor, following a growing file, reading the
+/// memory of a live process, or a memory image loaded from records.
 pub enum InputSource {
     Mmap(MmapReader),
     Stream(BufReader<Box<dyn Read + Send>>),
+    /// Decompressed bytes; offsets count decompressed bytes.
+    Compressed {
+        reader: BufReader<Box<dyn Read + Send>>,
+        compression: Compression,
+    },
+    /// A file read as it grows; it never ends.
+    Follow(Follower),
+    /// Process memory; offsets are virtual addresses.
+    Memory(MemoryReader),
+    /// Records loaded with `--import`; offsets are record addresses.
+    Image(Image),
 }
 
 impl InputSource {
-    /// Create input source from a file path.
-    pub fn from_file(path: &str) -> io::Result<Self> {
-        let file = File::open(path)?;
+    /// How the input is read, for display in headers.
+    pub fn description(&self) -> String {
+        match self {
+            InputSource::Mmap(_) => "memory-mapped".to_string(),
+            InputSource::Stream(_) => "streamed".to_string(),
+            InputSource::Compressed { compression, .. } => {
+                format!("{}-compressed", compression.name())
+            }
+            InputSource::Follow(_) => "followed".to_string(),
+            InputSource::Memory(_) => "process memory".to_string(),
+            InputSource::Image(_) => "loaded from records".to_string(),
+        }
+    }
+
+    /// Turn any source into a plain reader.
+    pub fn into_reader(self) -> Box<dyn Read + Send> {
+        match self {
+            InputSource::Mmap(mmap) => Box::new(mmap),
+            InputSource::Stream(reader) | InputSource::Compressed { reader, .. } => {
+                Box::new(reader)
+            }
+            InputSource::Follow(follower) => Box::new(follower),
+            InputSource::Memory(memory) => Box::new(memory),
+            InputSource::Image(image) => Box::new(image),
+        }
+    }
+}
+
+/// An opened input together with its position in the underlying file.
+pub struct Input {
+    pub source: InputSource,
+    /// Absolute offset of the first byte produced by `source`.
+    pub start: u64,
+    /// Number of bytes `source` will produce, when known in advance.
+    /// For pipes cut short by a count this is an upper bound.
+    pub len: Option<u64>,
+}
+
+impl Input {
+    /// Open a file path, decompressing it as `decompress` says, restricted to `range`.
+    pub fn from_file(path: &str, range: &ByteRange, decompress: Decompress) -> io::Result<Self> {
+        let mut file = File::open(path)?;
         let metadata = file.metadata()?;
         let size = metadata.len();
 
+        if !metadata.is_file() {
+            return Self::from_pipe(file, range, decompress);
+        }
+
+        if decompress != Decompress::None {
+            let head = read_head(&mut file)?;
+            file.seek(SeekFrom::Start(0))?;
+            if let Some(compression) = decompress.resolve(&head) {
+                return Self::decompressed(file, compression, range);
+            }
+        }
+
         // Use mmap for large regular files, streaming for small files and pipes.
-        if size >= MMAP_THRESHOLD && metadata.is_file() {
-            Ok(InputSource::Mmap(MmapReader::new(&file)?))
-        } else {
-            Ok(InputSource::Stream(BufReader::with_capacity(
-                READ_BUFFER_SIZE,
-                Box::new(file),
-            )))
+        // Once every mapping slot is taken (`--concat` over many files),
+        // files are streamed, as a mapping without protection would let a
+        // file shrinking under it kill the process.
+        if size >= MMAP_THRESHOLD && metadata.is_file() && sigbus::has_room() {
+            let (start, end) = range.resolve(size);
+            let mut mmap = MmapReader::new(&file)?;
+            mmap.restrict(start as usize, end as usize);
+            mmap.find_holes(&file);
+            return Ok(Input {
+                source: InputSource::Mmap(mmap),
+                start,
+                len: Some(end - start),
+            });
+        }
+
+        let (start, end) = range.resolve(size);
+        file.seek(SeekFrom::Start(start))?;
+        Ok(Input {
+            source: stream(file, Some(end - start)),
+            start,
+            len: Some(end - start),
+        })
+    }
+
+    /// Open stdin, decompressing it as `decompress` says, restricted to `range`.
+    pub fn from_stdin(range: &ByteRange, decompress: Decompress) -> io::Result<Self> {
+        Self::from_pipe(io::stdin(), range, decompress)
+    }
+
+    /// Open a pipe or other unseekable reader, peeking at its first bytes
+    /// to detect compression.
+    fn from_pipe<R: Read + Send + 'static>(
+        mut reader: R,
+        range: &ByteRange,
+        decompress: Decompress,
+    ) -> io::Result<Self> {
+        if decompress == Decompress::None {
+            return Self::from_unseekable(reader, range);
+        }
+
+        let head = read_head(&mut reader)?;
+        let compression = decompress.resolve(&head);
+        let reader = Cursor::new(head).chain(reader);
+        match compression {
+            Some(compression) => Self::decompressed(reader, compression, range),
+            None => Self::from_unseekable(reader, range),
+        }
+    }
+
+    /// Decompress `reader` and restrict the decompressed bytes to `range`.
+    fn decompressed<R: Read + Send + 'static>(
+        reader: R,
+        compression: Compression,
+        range: &ByteRange,
+    ) -> io::Result<Self> {
+        let input = Self::from_unseekable(compression.decoder(reader)?, range)?;
+        let source = match input.source {
+            InputSource::Stream(reader) => InputSource::Compressed {
+                reader,
+                compression,
+            },
+            source => source,
+        };
+        Ok(Input { source, ..input })
+    }
+
+    /// Follow a growing file from the start of `range` within its current contents.
+    pub fn follow(path: &str, range: &ByteRange) -> io::Result<Self> {
+        let follower = Follower::open(path, range)?;
+        Ok(Input {
+            start: follower.position(),
+            source: InputSource::Follow(follower),
+            len: None,
+        })
+    }
+
+    /// Read a mapping of process `pid`, restricted to `range` within it.
+    pub fn memory(pid: u32, mapping: &Mapping, range: &ByteRange) -> io::Result<Self> {
+        let (start, end) = range.resolve(mapping.end - mapping.start);
+        let start = mapping.start + start;
+        let end = mapping.start + end;
+        Ok(Input {
+            source: InputSource::Memory(MemoryReader::open(pid, start, end)?),
+            start,
+            len: Some(end - start),
+        })
+    }
+
+    /// Open a file path, or stdin if `path` is `None`, as `encoding` text
+    /// and restrict the decoded bytes to `range`.
+    pub fn decoded(path: Option<&str>, encoding: Encoding, range: &ByteRange) -> io::Result<Self> {
+        let reader = BufReader::with_capacity(READ_BUFFER_SIZE, open_reader(path)?);
+        Self::from_unseekable(Decoder::new(reader, encoding), range)
+    }
+
+    /// Load Intel HEX or S-record files from a path, or stdin if `path` is
+    /// `None`, into a memory image restricted to `range`. Offsets are the
+    /// record addresses; gaps between records are reported and passed on
+    /// like holes of a sparse file.
+    pub fn imported(
+        path: Option<&str>,
+        format: RecordFormat,
+        range: &ByteRange,
+    ) -> io::Result<Self> {
+        let reader = BufReader::with_capacity(READ_BUFFER_SIZE, open_reader(path)?);
+        let mut image = records::load(reader, format)?;
+        for (start, end) in image.gaps() {
+            eprintln!(
+                "Gap {:#x}..{:#x} ({} bytes) has no records",
+                start,
+                end,
+                end - start
+            );
         }
+
+        let (start, end) = range.resolve(image.len());
+        let start = image.start() + start;
+        let end = image.start() + end;
+        image.restrict(start, end);
+        Ok(Input {
+            source: InputSource::Image(image),
+            start,
+            len: Some(end - start),
+        })
     }
 
-    /// Create input source from stdin.
-    pub fn from_stdin() -> Self {
-        InputSource::Stream(BufReader::with_capacity(
-            READ_BUFFER_SIZE,
-            Box::new(io::stdin()),
-        ))
+    /// Join already opened inputs into one continuous stream restricted to
+    /// `range`. A read error ends that input early: it is passed to
+    /// `on_error` with the input's name, and the next input follows.
+    pub fn concat(
+        inputs: Vec<(String, Input)>,
+        range: &ByteRange,
+        on_error: ErrorHandler,
+    ) -> io::Result<Self> {
+        let total = inputs
+            .iter()
+            .map(|(_, input)| input.len)
+            .sum::<Option<u64>>();
+        let reader = ConcatReader {
+            inputs: inputs
+                .into_iter()
+                .map(|(name, input)| (name, input.source.into_reader()))
+                .collect(),
+            on_error,
+        };
+
+        let mut input = Self::from_unseekable(reader, range)?;
+        if input.len.is_none() {
+            input.len = total.map(|total| total.saturating_sub(input.start));
+        }
+        Ok(input)
     }
+
+    /// Restrict a pipe or other unseekable reader to `range`.
+    fn from_unseekable<R: Read + Send + 'static>(
+        mut reader: R,
+        range: &ByteRange,
+    ) -> io::Result<Self> {
+        if let Some(tail) = range.tail {
+            let (data, total) = read_tail(&mut reader, tail.max_len())?;
+            // Now that the total length is known, narrow the buffered window.
+            let (start, end) = range.resolve(total);
+            let buffered_start = total - data.len() as u64;
+            let mut cursor = Cursor::new(data);
+            cursor.set_position(start - buffered_start);
+            return Ok(Input {
+                source: stream(cursor, Some(end - start)),
+                start,
+                len: Some(end - start),
+            });
+        }
+
+        let start = discard(&mut reader, range.skip)?;
+        Ok(Input {
+            source: stream(reader, range.count),
+            start,
+            len: range.count,
+        })
+    }
+}
+
+/// Callback receiving an input's name and the error that ended it.
+pub type ErrorHandler = Box<dyn FnMut(&str, io::Error) + Send>;
+
+/// Reader over several inputs in turn, used by `Input::concat`.
+struct ConcatReader {
+    inputs: VecDeque<(String, Box<dyn Read + Send>)>,
+    on_error: ErrorHandler,
+}
+
+impl Read for ConcatReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while let Some((name, reader)) = self.inputs.front_mut() {
+            match reader.read(buf) {
+                Ok(0) => {}
+                Ok(n) => return Ok(n),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => (self.on_error)(name, e),
+            }
+            self.inputs.pop_front();
+        }
+        Ok(0)
+    }
+}
+
+/// Open a file path, or stdin if `path` is `None`, as a plain reader.
+fn open_reader(path: Option<&str>) -> io::Result<Box<dyn Read + Send>> {
+    Ok(match path {
+        Some(path) => Box::new(File::open(path)?),
+        None => Box::new(io::stdin()),
+    })
+}
+
+/// Wrap a reader as a buffered stream, limited to `count` bytes if given.
+fn stream<R: Read + Send + 'static>(reader: R, count: Option<u64>) -> InputSource {
+    let reader: Box<dyn Read + Send> = match count {
+        Some(count) => Box::new(reader.take(count)),
+        None => Box::new(reader),
+    };
+    InputSource::Stream(BufReader::with_capacity(READ_BUFFER_SIZE, reader))
+}
+
+/// Read to EOF keeping only the last `keep` bytes in a ring buffer.
+/// Returns the kept bytes and the total number of bytes read.
+fn read_tail<R: Read>(reader: &mut R, keep: u64) -> io::Result<(Vec<u8>, u64)> {
+    let keep = usize::try_from(keep).unwrap_or(usize::MAX);
+    let mut ring = VecDeque::with_capacity(keep.min(READ_BUFFER_SIZE));
+    let mut buffer = vec![0u8; READ_BUFFER_SIZE];
+    let mut total = 0u64;
+
+    loop {
+        let n = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        total += n as u64;
+
+        let chunk = &buffer[n.saturating_sub(keep)..n];
+        let overflow = (ring.len() + chunk.len()).saturating_sub(keep);
+        ring.drain(..overflow);
+        ring.extend(chunk);
+    }
+
+    Ok((Vec::from(ring), total))
+}
+
+/// Read and drop up to `n` bytes from a non-seekable reader.
+/// Returns the number of bytes actually skipped.
+fn discard<R: Read>(reader: &mut R, n: u64) -> io::Result<u64> {
+    io::copy(&mut reader.take(n), &mut io::sink())
 }
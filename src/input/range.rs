@@ -0,0 +1,93 @@
+//! Byte range selection for dumping part of an input.
+
+/// A window into the input: skip `skip` bytes (or start at the `tail`),
+/// then take at most `count`.
+#[derive(Clone, Copy, Default)]
+pub struct ByteRange {
+    pub skip: u64,
+    pub count: Option<u64>,
+    pub tail: Option<Tail>,
+}
+
+/// Selection of the end of the input.
+#[derive(Clone, Copy)]
+pub enum Tail {
+    /// The last N bytes.
+    Bytes(u64),
+    /// The last N dump lines, aligned to line boundaries of the full dump.
+    Lines { lines: u64, line_bytes: u64 },
+}
+
+impl Tail {
+    /// Number of trailing bytes selected from an input of `len` bytes.
+    pub fn len(&self, len: u64) -> u64 {
+        match *self {
+            Tail::Bytes(n) => n.min(len),
+            Tail::Lines { lines, line_bytes } => {
+                if lines == 0 || len == 0 {
+                    return 0;
+                }
+                // The final line may be partial; the rest are full lines.
+                let last = match len % line_bytes {
+                    0 => line_bytes,
+                    partial => partial,
+                };
+                (lines - 1)
+                    .saturating_mul(line_bytes)
+                    .saturating_add(last)
+                    .min(len)
+            }
+        }
+    }
+
+    /// Upper bound of `len` over all input sizes, for buffering unseekable input.
+    pub fn max_len(&self) -> u64 {
+        match *self {
+            Tail::Bytes(n) => n,
+            Tail::Lines { lines, line_bytes } => lines.saturating_mul(line_bytes),
+        }
+    }
+}
+
+impl ByteRange {
+    /// Clamp the range to an input of `len` bytes, returning `(start, end)`.
+    pub fn resolve(&self, len: u64) -> (u64, u64) {
+        let start = match self.tail {
+            Some(tail) => len - tail.len(len),
+            None => self.skip.min(len),
+        };
+        let end = match self.count {
+            Some(count) => start.saturating_add(count).min(len),
+            None => len,
+        };
+        (start, end)
+    }
+}
+
+/// Parse a byte size such as `4096`, `0x1000`, `0o777` or `4K`.
+/// The `K`, `M` and `G` suffixes are binary multiples (1024).
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let (digits, multiplier) = match s.as_bytes().last() {
+        Some(b'k' | b'K') => (&s[..s.len() - 1], 1u64 << 10),
+        Some(b'm' | b'M') => (&s[..s.len() - 1], 1u64 << 20),
+        Some(b'g' | b'G') => (&s[..s.len() - 1], 1u64 << 30),
+        _ => (s, 1),
+    };
+
+    let value = if let Some(hex) = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        u64::from_str_radix(hex, 16)
+    } else if let Some(oct) = digits.strip_prefix("0o") {
+        u64::from_str_radix(oct, 8)
+    } else {
+        digits.parse::<u64>()
+    }
+    .map_err(|_| format!("invalid size: {}", s))?;
+
+    value
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("size too large: {}", s))
+}
@@ -0,0 +1,273 @@
+//! Intel HEX and S-record import: load records into a memory image.
+
+use super::Segment;
+use crate::hex::RecordFormat;
+use std::io::{self, BufRead, Read};
+
+/// Memory image assembled from records: the loaded runs of bytes, with
+/// the addresses between them left out rather than zero-filled.
+pub struct Image {
+    /// Runs of loaded bytes as `(address, bytes)`, in address order and
+    /// apart from each other.
+    segments: Vec<(u64, Vec<u8>)>,
+    /// Visible window `start..end` of addresses.
+    start: u64,
+    end: u64,
+    /// Address of the next byte handed out by `read`.
+    position: u64,
+}
+
+impl Image {
+    /// Address of the first visible byte.
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// Number of addresses from the lowest to the highest loaded one.
+    pub fn len(&self) -> u64 {
+        self.end - self.start
+    }
+
+    /// Address ranges `start..end` between records that no record covered.
+    pub fn gaps(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.segments
+            .windows(2)
+            .map(|pair| (pair[0].0 + pair[0].1.len() as u64, pair[1].0))
+    }
+
+    /// Narrow the visible window to addresses `start..end`.
+    pub fn restrict(&mut self, start: u64, end: u64) {
+        self.start = start;
+        self.end = end;
+        self.position = start;
+    }
+
+    /// Pass the window to `process` as loaded bytes and the gaps between
+    /// them, in address order.
+    pub fn for_each_segment<F>(&self, mut process: F) -> io::Result<()>
+    where
+        F: FnMut(Segment<'_>) -> io::Result<()>,
+    {
+        let mut position = self.start;
+        for (address, bytes) in &self.segments {
+            let first = (*address).max(position);
+            let last = (address + bytes.len() as u64).min(self.end);
+            if first >= last {
+                continue;
+            }
+            if first > position {
+                process(Segment::Hole(first - position))?;
+            }
+            process(Segment::Data(
+                &bytes[(first - address) as usize..(last - address) as usize],
+            ))?;
+            position = last;
+        }
+        if self.end > position {
+            process(Segment::Hole(self.end - position))?;
+        }
+        Ok(())
+    }
+}
+
+impl Read for Image {
+    /// Read the window with gaps as zeros, as a flat binary image has them.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let want = (self.end - self.position).min(buf.len() as u64) as usize;
+        if want == 0 {
+            return Ok(0);
+        }
+
+        // The run holding the position, or the gap before the next run
+        let next = self
+            .segments
+            .partition_point(|(address, bytes)| address + bytes.len() as u64 <= self.position);
+        let n = match self.segments.get(next) {
+            Some((address, bytes)) if *address <= self.position => {
+                let from = (self.position - address) as usize;
+                let n = want.min(bytes.len() - from);
+                buf[..n].copy_from_slice(&bytes[from..from + n]);
+                n
+            }
+            Some((address, _)) => {
+                let n = want.min((address - self.position) as usize);
+                buf[..n].fill(0);
+                n
+            }
+            None => {
+                buf[..want].fill(0);
+                want
+            }
+        };
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+/// Parse every record in `reader`, validating checksums.
+pub fn load<R: BufRead>(reader: R, format: RecordFormat) -> io::Result<Image> {
+    let mut segments: Vec<(u64, Vec<u8>)> = Vec::new();
+    let mut parser = Parser::default();
+
+    for (index, line) in reader.split(b'\n').enumerate() {
+        let line = line?;
+        let line = line.trim_ascii();
+        if line.is_empty() {
+            continue;
+        }
+
+        let parsed = match format {
+            RecordFormat::Ihex => parser.ihex(line),
+            RecordFormat::Srec => parser.srec(line),
+        };
+        let record = parsed.map_err(|msg| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {}: {}", index + 1, msg),
+            )
+        })?;
+
+        match record {
+            Parsed::Data(address, bytes) => match segments.last_mut() {
+                // Consecutive records usually continue the previous one
+                Some((start, data)) if *start + data.len() as u64 == address => {
+                    data.extend_from_slice(&bytes)
+                }
+                _ => segments.push((address, bytes)),
+            },
+            Parsed::Other => {}
+            Parsed::End => break,
+        }
+    }
+
+    segments.sort_by_key(|&(address, _)| address);
+
+    // Runs that touch are joined, so only real gaps separate them
+    let mut joined: Vec<(u64, Vec<u8>)> = Vec::with_capacity(segments.len());
+    for (address, bytes) in segments {
+        match joined.last_mut() {
+            Some((start, data)) if address < *start + data.len() as u64 => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("records overlap at address {:#x}", address),
+                ));
+            }
+            Some((start, data)) if address == *start + data.len() as u64 => {
+                data.extend_from_slice(&bytes)
+            }
+            _ => joined.push((address, bytes)),
+        }
+    }
+
+    let start = joined.first().map_or(0, |&(address, _)| address);
+    let end = joined
+        .last()
+        .map_or(0, |(address, bytes)| address + bytes.len() as u64);
+    Ok(Image {
+        segments: joined,
+        start,
+        end,
+        position: start,
+    })
+}
+
+/// Result of parsing one record.
+enum Parsed {
+    /// Data bytes at an absolute address.
+    Data(u64, Vec<u8>),
+    /// A record that carries no data (header, count, start address).
+    Other,
+    /// End-of-file or termination record.
+    End,
+}
+
+/// Parser state carried between records.
+#[derive(Default)]
+struct Parser {
+    /// Base added to Intel HEX record addresses by types 02 and 04.
+    base: u64,
+}
+
+impl Parser {
+    /// Parse `:LLAAAATT<data>CC`.
+    fn ihex(&mut self, line: &[u8]) -> Result<Parsed, String> {
+        let hex = line
+            .strip_prefix(b":")
+            .ok_or("record does not start with ':'")?;
+        let bytes = decode_hex(hex)?;
+        if bytes.len() < 5 || bytes.len() != 5 + bytes[0] as usize {
+            return Err("record length does not match its byte count".into());
+        }
+        if bytes.iter().fold(0u8, |sum, &b| sum.wrapping_add(b)) != 0 {
+            return Err("checksum mismatch".into());
+        }
+
+        let address = u16::from_be_bytes([bytes[1], bytes[2]]) as u64;
+        let data = &bytes[4..bytes.len() - 1];
+        match bytes[3] {
+            0x00 => Ok(Parsed::Data(self.base + address, data.to_vec())),
+            0x01 => Ok(Parsed::End),
+            0x02 | 0x04 if data.len() == 2 => {
+                let value = u16::from_be_bytes([data[0], data[1]]) as u64;
+                self.base = if bytes[3] == 0x02 {
+                    value << 4
+                } else {
+                    value << 16
+                };
+                Ok(Parsed::Other)
+            }
+            0x03 | 0x05 => Ok(Parsed::Other),
+            kind => Err(format!("invalid record type {:02X}", kind)),
+        }
+    }
+
+    /// Parse `S<type><count><address><data><checksum>`.
+    fn srec(&mut self, line: &[u8]) -> Result<Parsed, String> {
+        let (kind, hex) = match line {
+            [b'S' | b's', kind @ b'0'..=b'9', hex @ ..] => (kind - b'0', hex),
+            _ => return Err("record does not start with 'S' and a type digit".into()),
+        };
+        let bytes = decode_hex(hex)?;
+        if bytes.is_empty() || bytes.len() != 1 + bytes[0] as usize {
+            return Err("record length does not match its byte count".into());
+        }
+        if bytes.iter().fold(0u8, |sum, &b| sum.wrapping_add(b)) != 0xFF {
+            return Err("checksum mismatch".into());
+        }
+
+        let address_bytes = match kind {
+            0 | 1 | 5 | 9 => 2,
+            2 | 6 | 8 => 3,
+            3 | 7 => 4,
+            _ => return Err(format!("invalid record type S{}", kind)),
+        };
+        if bytes.len() < 2 + address_bytes {
+            return Err("record too short for its address".into());
+        }
+
+        let address = bytes[1..1 + address_bytes]
+            .iter()
+            .fold(0u64, |acc, &b| acc << 8 | b as u64);
+        let data = &bytes[1 + address_bytes..bytes.len() - 1];
+        match kind {
+            1..=3 => Ok(Parsed::Data(address, data.to_vec())),
+            7..=9 => Ok(Parsed::End),
+            _ => Ok(Parsed::Other),
+        }
+    }
+}
+
+/// Decode pairs of hex digits.
+fn decode_hex(hex: &[u8]) -> Result<Vec<u8>, String> {
+    if !hex.len().is_multiple_of(2) {
+        return Err("odd number of hex digits".into());
+    }
+    hex.chunks_exact(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                .ok_or_else(|| "invalid hex digit".to_string())
+        })
+        .collect()
+}
@@ -0,0 +1,163 @@
+//! SIGBUS recovery for mapped files that shrink while being read.
+//!
+//! Touching a page of a mapping past the end of its file raises SIGBUS.
+//! The handler maps a page of zeros over the faulting page of a registered
+//! mapping and records the fault, so the reader can report the truncation
+//! instead of the process being killed.
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Once;
+
+    /// Mappings that can be open at once (`--concat` maps every file up front).
+    const SLOTS: usize = 64;
+
+    struct Slot {
+        start: AtomicUsize,
+        end: AtomicUsize,
+        /// Address of the first page that faulted, or 0.
+        fault: AtomicUsize,
+    }
+
+    #[allow(clippy::declare_interior_mutable_const)]
+    const EMPTY: Slot = Slot {
+        start: AtomicUsize::new(0),
+        end: AtomicUsize::new(0),
+        fault: AtomicUsize::new(0),
+    };
+
+    static REGISTERED: [Slot; SLOTS] = [EMPTY; SLOTS];
+    static PAGE_SIZE: AtomicUsize = AtomicUsize::new(4096);
+    static INSTALL: Once = Once::new();
+
+    extern "C" fn on_sigbus(_: libc::c_int, info: *mut libc::siginfo_t, _: *mut libc::c_void) {
+        // SAFETY: the kernel passes a valid siginfo for SA_SIGINFO handlers.
+        let addr = unsafe { (*info).si_addr() } as usize;
+        let page_size = PAGE_SIZE.load(Ordering::Relaxed);
+        for slot in &REGISTERED {
+            let start = slot.start.load(Ordering::Acquire);
+            let end = slot.end.load(Ordering::Acquire);
+            if start == 0 || addr < start || addr >= end {
+                continue;
+            }
+
+            let page = addr & !(page_size - 1);
+            // SAFETY: the page lies in a mapping we own; replacing it with
+            // zeros only changes what the retried read sees.
+            let patched = unsafe {
+                libc::mmap(
+                    page as *mut libc::c_void,
+                    page_size,
+                    libc::PROT_READ,
+                    libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_FIXED,
+                    -1,
+                    0,
+                )
+            };
+            if patched != libc::MAP_FAILED {
+                let _ = slot.fault.compare_exchange(
+                    0,
+                    page.max(start),
+                    Ordering::AcqRel,
+                    Ordering::Relaxed,
+                );
+                return;
+            }
+        }
+
+        // Not one of ours: the retried access ends the process as usual
+        // SAFETY: resetting a signal disposition is async-signal-safe.
+        unsafe {
+            libc::signal(libc::SIGBUS, libc::SIG_DFL);
+        }
+    }
+
+    fn install() {
+        INSTALL.call_once(|| {
+            // SAFETY: plain libc calls with a zeroed, then filled in, sigaction.
+            unsafe {
+                let page_size = libc::sysconf(libc::_SC_PAGESIZE);
+                if page_size > 0 {
+                    PAGE_SIZE.store(page_size as usize, Ordering::Relaxed);
+                }
+
+                let mut action: libc::sigaction = std::mem::zeroed();
+                action.sa_sigaction = on_sigbus as *const () as usize;
+                action.sa_flags = libc::SA_SIGINFO;
+                libc::sigemptyset(&mut action.sa_mask);
+                libc::sigaction(libc::SIGBUS, &action, std::ptr::null_mut());
+            }
+        });
+    }
+
+    pub fn register(start: usize, len: usize) -> Option<usize> {
+        if start == 0 || len == 0 {
+            return None;
+        }
+        install();
+        REGISTERED.iter().position(|slot| {
+            if slot
+                .start
+                .compare_exchange(0, start, Ordering::AcqRel, Ordering::Relaxed)
+                .is_err()
+            {
+                return false;
+            }
+            slot.fault.store(0, Ordering::Release);
+            slot.end.store(start + len, Ordering::Release);
+            true
+        })
+    }
+
+    pub fn has_room() -> bool {
+        REGISTERED
+            .iter()
+            .any(|slot| slot.start.load(Ordering::Acquire) == 0)
+    }
+
+    pub fn unregister(index: usize) {
+        let slot = &REGISTERED[index];
+        slot.end.store(0, Ordering::Release);
+        slot.start.store(0, Ordering::Release);
+    }
+
+    pub fn fault(index: usize) -> Option<usize> {
+        match REGISTERED[index].fault.load(Ordering::Acquire) {
+            0 => None,
+            page => Some(page),
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod imp {
+    pub fn register(_: usize, _: usize) -> Option<usize> {
+        None
+    }
+
+    pub fn has_room() -> bool {
+        true
+    }
+
+    pub fn unregister(_: usize) {}
+
+    pub fn fault(_: usize) -> Option<usize> {
+        None
+    }
+}
+
+/// Protect the mapping at `start..start + len`. Returns a handle for
+/// `unregister` and `fault`, or `None` if it cannot be protected.
+pub use imp::register;
+
+/// Whether another mapping can be protected. Where none can be, this is
+/// always true, so files are mapped as they would be without protection.
+pub use imp::has_room;
+
+/// Stop protecting a mapping before it is unmapped.
+pub use imp::unregister;
+
+/// Address of the first page of a registered mapping that was replaced by
+/// zeros because its file had shrunk.
+pub use imp::fault;
@@ -0,0 +1,48 @@
+//! Hole detection for sparse files, so holes are skipped instead of read.
+
+use std::fs::File;
+
+/// Holes of `file` within `start..end`, as `(start, end)` file offsets in
+/// ascending order. Empty if the file system cannot report holes.
+#[cfg(target_os = "linux")]
+pub fn holes(file: &File, start: u64, end: u64) -> Vec<(u64, u64)> {
+    use std::os::unix::io::AsRawFd;
+
+    let fd = file.as_raw_fd();
+    let seek = |offset: u64, whence: libc::c_int| {
+        // SAFETY: lseek only moves the offset of a descriptor we own.
+        let result = unsafe { libc::lseek64(fd, offset as libc::off64_t, whence) };
+        if result < 0 {
+            Err(std::io::Error::last_os_error())
+        } else {
+            Ok(result as u64)
+        }
+    };
+
+    let mut holes = Vec::new();
+    let mut position = start;
+    while position < end {
+        let data = match seek(position, libc::SEEK_DATA) {
+            Ok(data) => data.min(end),
+            // No data after `position`: the rest of the file is one hole
+            Err(e) if e.raw_os_error() == Some(libc::ENXIO) => end,
+            Err(_) => return Vec::new(),
+        };
+        if data > position {
+            holes.push((position, data));
+        }
+        if data >= end {
+            break;
+        }
+        position = match seek(data, libc::SEEK_HOLE) {
+            Ok(hole) => hole,
+            Err(_) => break,
+        };
+    }
+    holes
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn holes(_: &File, _: u64, _: u64) -> Vec<(u64, u64)> {
+    Vec::new()
+}
@@ -1,19 +1,49 @@
 //! bred - The fastest binary file reader with coloring.
 
-use clap::Parser;
-use std::io::{self, stdout, BufWriter, Read, Write};
+use clap::{ArgGroup, Parser};
+use std::fmt::Display;
+use std::fs;
+use std::io::{self, stdout, BufReader, BufWriter, IsTerminal, Read, Write};
+use std::path::Path;
 use std::process::exit;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::thread;
 
 mod hex;
 mod input;
 mod output;
+mod reverse;
+mod search;
 
-use hex::{BinaryDumper, DumpConfig, HexDumper, TextDumper};
-use input::{InputSource, READ_BUFFER_SIZE, WRITE_BUFFER_SIZE};
+use hex::{
+    hex_column_width, load_rules, parse_pattern_rule, parse_range_rule, parse_value_rule,
+    write_match, BinaryDumper, ByteFormat, DumpConfig, Dumper, EncodeDumper, Encoding, Endian,
+    HexDumper, HexLayout, HighlightDumper, Highlights, IncludeDumper, IntelHexDumper, Language,
+    ParallelDumper, PlainHexDumper, RecordFormat, Rule, SrecDumper, SrecType, TextDumper,
+    TypedDumper, ValueType, MAX_RECORD_BYTES,
+};
+
+use input::{
+    maps, parse_region, parse_size, ByteRange, Compression, Decompress, FollowEvent, Input,
+    InputSource, MemoryChunk, Region, Segment, Tail, READ_BUFFER_SIZE, WRITE_BUFFER_SIZE,
+};
+use output::color::ColorChoice;
+use output::offset::OffsetFormat;
+use output::theme::{ColorDepth, Coloring, Palette, Theme};
+use reverse::{DumpKind, ReverseConfig, Reverser};
+use search::{
+    search_memory, search_reader, search_slice, Context, LineBreaks, Needle, Searcher, Sink,
+};
 
 const DEFAULT_HEX_LINE_LENGTH: usize = 16;
 const DEFAULT_BINARY_LINE_LENGTH: usize = 64;
 const DEFAULT_TEXT_LINE_LENGTH: usize = 64;
+const DEFAULT_PLAIN_LINE_LENGTH: usize = 30;
+const DEFAULT_INCLUDE_LINE_LENGTH: usize = 12;
+const DEFAULT_ENCODE_LINE_LENGTH: usize = 76;
+const XXD_OFFSET_WIDTH: usize = 8;
+const XXD_GROUP_SIZE: usize = 2;
 
 #[derive(Parser)]
 #[command(
@@ -21,9 +51,31 @@ const DEFAULT_TEXT_LINE_LENGTH: usize = 64;
     author = "Mano Rajesh",
     about = "The fastest binary file reader with coloring"
 )]
+#[command(group(
+    ArgGroup::new("needle")
+        .args(["find", "find_text", "find_utf16", "find_regex"])
+        .conflicts_with_all(["follow", "reverse", "include", "encode", "ihex", "srec"])
+))]
 struct Args {
-    /// The file to read or stdin if not provided
-    file: Option<String>,
+    /// The files to read, or stdin if none are given or for "-"
+    files: Vec<String>,
+
+    /// Dump all files as one continuous stream with continuous offsets
+    #[arg(long)]
+    concat: bool,
+
+    /// Dump the memory of a live process instead of files (Linux)
+    #[arg(long, value_name = "PID", conflicts_with_all = ["files", "concat", "follow", "reverse", "decode", "import"])]
+    pid: Option<u32>,
+
+    /// Memory to dump with --pid: a mapping name like heap, stack or libc, or hex
+    /// addresses START-END; without it the mappings are listed
+    #[arg(long, value_name = "REGION", value_parser = parse_region, requires = "pid")]
+    region: Option<Region>,
+
+    /// Keep dumping bytes appended to the file, like `tail -f`
+    #[arg(short = 'f', long, conflicts_with_all = ["concat", "reverse", "count", "decode", "import"])]
+    follow: bool,
 
     /// Number of bytes/bits per line [default for hex: 16] [default: 64]
     #[arg(short = 'l', long = "length", value_name = "LENGTH")]
@@ -33,143 +85,1157 @@ struct Args {
     #[arg(short = 'x', long)]
     hex: bool,
 
-    /// Print in color
-    #[arg(short = 'G', long)]
-    color: bool,
+    /// Print bytes in octal
+    #[arg(short = 'o', long, conflicts_with_all = ["hex", "decimal"])]
+    octal: bool,
+
+    /// Print bytes in unsigned decimal
+    #[arg(short = 'd', long, conflicts_with = "hex")]
+    decimal: bool,
+
+    /// Show an ASCII column next to the hex bytes (like hexdump -C)
+    #[arg(short = 'C', long)]
+    canonical: bool,
+
+    /// Print in xxd's layout: 8-digit offset, colon, 2-byte words and an ASCII column
+    #[arg(long, conflicts_with_all = ["octal", "decimal"])]
+    xxd: bool,
+
+    /// Print continuous plain hex without offsets (like xxd -p)
+    #[arg(short = 'p', long)]
+    plain: bool,
+
+    /// Print a source-code array declaration for this language (like xxd -i)
+    #[arg(short = 'i', long, value_enum, value_name = "LANG")]
+    include: Option<Language>,
+
+    /// Declare a string literal of \xNN escapes instead of an array (with --include)
+    #[arg(long, requires = "include")]
+    string: bool,
+
+    /// Variable name for --include [default: sanitized input path]
+    #[arg(long, value_name = "NAME", requires = "include")]
+    name: Option<String>,
+
+    /// Encode the input as text, wrapped at --length characters [default: 76]
+    #[arg(long, value_enum, value_name = "ENCODING")]
+    encode: Option<Encoding>,
+
+    /// Decode the input from this text encoding before dumping it
+    #[arg(long, value_enum, value_name = "ENCODING")]
+    decode: Option<Encoding>,
+
+    /// Print Intel HEX records, addressed from the base address
+    #[arg(long)]
+    ihex: bool,
+
+    /// Print Motorola S-records, addressed from the base address
+    #[arg(long, conflicts_with = "ihex")]
+    srec: bool,
+
+    /// S-record address width [default: smallest that fits]
+    #[arg(long, value_enum, value_name = "TYPE", requires = "srec")]
+    srec_type: Option<SrecType>,
+
+    /// Decompress gzip, zstd or xz input detected by its magic bytes, or force a format
+    /// [default: auto; none with --encode, --include, --ihex and --srec]
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    decompress: Option<Decompress>,
+
+    /// Load an Intel HEX or S-record file and dump the memory image it describes
+    #[arg(long, value_enum, value_name = "FORMAT", conflicts_with = "decode")]
+    import: Option<RecordFormat>,
+
+    /// Group hex bytes into words of this many bytes [default: 1, 2 with --xxd]
+    #[arg(short = 'g', long, value_name = "BYTES", value_parser = parse_group, conflicts_with_all = ["octal", "decimal"])]
+    group: Option<usize>,
+
+    /// Byte order of grouped words
+    #[arg(short = 'e', long, value_enum, value_name = "ORDER", default_value_t = Endian::Big)]
+    endian: Endian,
+
+    /// Print every line instead of collapsing repeated lines into '*' (hex mode)
+    #[arg(short = 'v', long)]
+    no_squeezing: bool,
+
+    /// Color bytes by class; -G alone means always. Without it only offsets are colored, on a terminal
+    #[arg(
+        short = 'G',
+        long,
+        value_enum,
+        value_name = "WHEN",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "always"
+    )]
+    color: Option<ColorChoice>,
+
+    /// Color theme: dark, light, high-contrast, a theme in ~/.config/bred/themes, or a theme file [default: from ~/.config/bred/config, else dark]
+    #[arg(long, value_name = "NAME")]
+    theme: Option<String>,
+
+    /// How --color colors bytes
+    #[arg(long, value_enum, value_name = "MODE", default_value_t = Coloring::Class)]
+    coloring: Coloring,
 
     /// Highlight space characters (0x20)
     #[arg(short = 's', long)]
     space: bool,
 
+    /// Highlight a byte value or values FIRST-LAST in a style (e.g. 0xff=red, 0x80-0x9f=blue)
+    #[arg(long, value_name = "VALUE=STYLE", value_parser = parse_value_rule)]
+    highlight: Vec<Rule>,
+
+    /// Highlight the bytes at printed offsets START..END (e.g. 0x100..0x140=blue)
+    #[arg(long, value_name = "RANGE=STYLE", value_parser = parse_range_rule)]
+    highlight_range: Vec<Rule>,
+
+    /// Highlight every occurrence of hex bytes or a quoted string (e.g. 'de ad be ef=magenta')
+    #[arg(long, value_name = "PATTERN=STYLE", value_parser = parse_pattern_rule)]
+    highlight_pattern: Vec<Rule>,
+
+    /// Load highlight rules from a file, before those given as options
+    #[arg(long, value_name = "FILE")]
+    highlight_file: Option<String>,
+
     /// Print in binary
     #[arg(short = 'b', long)]
     binary: bool,
+
+    /// Print values of this type in decimal columns (e.g. u8, i16le, u32be, f64le)
+    #[arg(short = 't', long = "type", value_name = "TYPE")]
+    value_type: Option<ValueType>,
+
+    /// Read a dump produced by bred (in the given mode) and write the original bytes.
+    /// Text dumps made with --space cannot be reversed
+    #[arg(short = 'r', long)]
+    reverse: bool,
+
+    /// Skip this many bytes from the start of the input (e.g. 512, 0x200, 4K)
+    #[arg(long, visible_alias = "offset", value_name = "BYTES", value_parser = parse_size)]
+    skip: Option<u64>,
+
+    /// Dump at most this many bytes (e.g. 256, 0x100, 1M)
+    #[arg(short = 'n', long, visible_alias = "length-bytes", value_name = "BYTES", value_parser = parse_size)]
+    count: Option<u64>,
+
+    /// Dump only the last BYTES of the input (e.g. 512, 4K)
+    #[arg(long, value_name = "BYTES", value_parser = parse_size, conflicts_with_all = ["skip", "tail_lines"])]
+    tail: Option<u64>,
+
+    /// Dump only the last N lines of the output (not in text mode, where
+    /// escapes make line breaks depend on all earlier bytes)
+    #[arg(long, value_name = "N", conflicts_with = "skip")]
+    tail_lines: Option<u64>,
+
+    /// Add this address to every printed offset (e.g. 0x400000)
+    #[arg(long, value_name = "ADDRESS", value_parser = parse_size, default_value = "0")]
+    base_address: u64,
+
+    /// Search for hex bytes instead of dumping (e.g. 'de ad ?? ef'); '?' matches any nibble, VV&MM compares the bits of MM
+    #[arg(long, value_name = "HEX", value_parser = search::parse_hex)]
+    find: Option<Needle>,
+
+    /// Search for a string
+    #[arg(long, value_name = "TEXT", value_parser = search::parse_text)]
+    find_text: Option<Needle>,
+
+    /// Search for a string in UTF-16 of either byte order
+    #[arg(long, value_name = "TEXT", value_parser = search::parse_utf16)]
+    find_utf16: Option<Needle>,
+
+    /// Search for a regular expression over bytes (e.g. 'PK\x03\x04')
+    #[arg(long, value_name = "REGEX", value_parser = search::parse_regex)]
+    find_regex: Option<Needle>,
+
+    /// Stop searching at the first match
+    #[arg(long, requires = "needle")]
+    first: bool,
+
+    /// Print only the number of matches
+    #[arg(long, requires = "needle", conflicts_with = "context")]
+    count_matches: bool,
+
+    /// Dump LINES lines before and after each match in the selected mode
+    #[arg(long, value_name = "LINES", requires = "needle")]
+    context: Option<u64>,
+
+    /// Threads formatting memory-mapped files in hex, binary and text modes [default: all cores]
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    threads: Option<u16>,
+
+    /// Radix of the offset column
+    #[arg(long, value_enum, value_name = "FORMAT", default_value_t = OffsetFormat::Hex)]
+    offset_format: OffsetFormat,
+}
+
+/// Output mode selected on the command line.
+#[derive(Clone, Copy)]
+enum Mode {
+    Bytes(ByteFormat),
+    Plain,
+    Binary,
+    Text,
+    Typed(ValueType),
+    Include(Language),
+    Encode(Encoding),
+    Records(RecordFormat),
+}
+
+impl Args {
+    /// The pattern to search for, if searching instead of dumping.
+    fn needle(&self) -> Option<&Needle> {
+        self.find
+            .as_ref()
+            .or(self.find_text.as_ref())
+            .or(self.find_utf16.as_ref())
+            .or(self.find_regex.as_ref())
+    }
+
+    /// How inputs are decompressed. Encodings, include files and record
+    /// files carry the bytes exactly as stored unless asked otherwise.
+    fn decompress(&self) -> Decompress {
+        self.decompress.unwrap_or(match self.mode() {
+            Mode::Encode(_) | Mode::Include(_) | Mode::Records(_) => Decompress::None,
+            _ => Decompress::Auto,
+        })
+    }
+
+    fn mode(&self) -> Mode {
+        if let Some(value_type) = self.value_type {
+            Mode::Typed(value_type)
+        } else if self.ihex {
+            Mode::Records(RecordFormat::Ihex)
+        } else if self.srec {
+            Mode::Records(RecordFormat::Srec)
+        } else if let Some(encoding) = self.encode {
+            Mode::Encode(encoding)
+        } else if let Some(language) = self.include {
+            Mode::Include(language)
+        } else if self.plain {
+            Mode::Plain
+        } else if self.hex || self.xxd {
+            Mode::Bytes(ByteFormat::Hex)
+        } else if self.octal {
+            Mode::Bytes(ByteFormat::Octal)
+        } else if self.decimal {
+            Mode::Bytes(ByteFormat::Decimal)
+        } else if self.binary {
+            Mode::Binary
+        } else {
+            Mode::Text
+        }
+    }
+}
+
+impl Mode {
+    /// Whether several inputs are introduced by header lines. Modes that
+    /// produce source code, encodings or record files are left unadorned.
+    fn shows_headers(self) -> bool {
+        matches!(
+            self,
+            Mode::Bytes(_) | Mode::Binary | Mode::Text | Mode::Typed(_)
+        )
+    }
+
+    fn default_line_length(self) -> usize {
+        match self {
+            Mode::Bytes(_) | Mode::Typed(_) | Mode::Records(_) => DEFAULT_HEX_LINE_LENGTH,
+            Mode::Plain => DEFAULT_PLAIN_LINE_LENGTH,
+            Mode::Include(_) => DEFAULT_INCLUDE_LINE_LENGTH,
+            Mode::Encode(_) => DEFAULT_ENCODE_LINE_LENGTH,
+            Mode::Binary => DEFAULT_BINARY_LINE_LENGTH,
+            Mode::Text => DEFAULT_TEXT_LINE_LENGTH,
+        }
+    }
+
+    /// Number of input bytes shown on one full line of output. Text mode
+    /// lines vary with escapes; there this is the one-char-per-byte width.
+    fn bytes_per_line(self, line_length: usize) -> u64 {
+        match self {
+            Mode::Binary => (line_length as u64 / 8).max(1),
+            Mode::Typed(value_type) => {
+                ((line_length / value_type.size).max(1) * value_type.size) as u64
+            }
+            _ => line_length as u64,
+        }
+    }
 }
 
 fn main() {
-    if let Err(e) = run() {
+    match run() {
+        Ok(true) => {}
+        // Failed inputs have already been reported
+        Ok(false) => exit(1),
+        Err(e) => {
+            report_error(&e);
+            exit(1);
+        }
+    }
+}
+
+fn report_error(e: &dyn Display) {
+    if ColorChoice::Auto.enabled(io::stderr().is_terminal()) {
         eprintln!("\x1b[31mError:\x1b[0m {}", e);
-        exit(1);
+    } else {
+        eprintln!("Error: {}", e);
+    }
+}
+
+/// Whether escape codes may be written. Offsets are gray on a terminal
+/// even without --color; never write escape codes into a pipe or file
+/// unless asked to.
+fn ansi(args: &Args) -> bool {
+    args.color
+        .unwrap_or_default()
+        .enabled(io::stdout().is_terminal())
+}
+
+/// Escape codes of the selected theme at the terminal's color depth,
+/// loaded on first use. Without escape codes no theme file is read.
+fn palette(args: &Args) -> io::Result<&'static Palette> {
+    static PALETTE: OnceLock<Palette> = OnceLock::new();
+    if let Some(palette) = PALETTE.get() {
+        return Ok(palette);
     }
+    if !ansi(args) {
+        return Ok(PALETTE.get_or_init(Palette::plain));
+    }
+    let theme = Theme::load(args.theme.as_deref())?;
+    Ok(PALETTE.get_or_init(|| theme.palette(ColorDepth::detect(), args.coloring)))
 }
 
-fn run() -> io::Result<()> {
+/// Highlight rules from `--highlight-file` and the options, loaded on first
+/// use. Without escape codes there is nothing to draw them with, so none
+/// are loaded.
+fn highlights(args: &Args) -> io::Result<&'static Highlights> {
+    static HIGHLIGHTS: OnceLock<Highlights> = OnceLock::new();
+    if let Some(highlights) = HIGHLIGHTS.get() {
+        return Ok(highlights);
+    }
+    if !ansi(args) {
+        let highlights = Highlights::new(&[], ColorDepth::detect())?;
+        return Ok(HIGHLIGHTS.get_or_init(|| highlights));
+    }
+    let mut rules = match &args.highlight_file {
+        Some(path) => load_rules(Path::new(path))?,
+        None => Vec::new(),
+    };
+    rules.extend(args.highlight.iter().cloned());
+    rules.extend(args.highlight_range.iter().cloned());
+    rules.extend(args.highlight_pattern.iter().cloned());
+    let mut highlights = Highlights::new(&rules, ColorDepth::detect())?;
+    if args.needle().is_some() {
+        highlights = highlights.with_match(palette(args)?.highlight.clone());
+    }
+    Ok(HIGHLIGHTS.get_or_init(|| highlights))
+}
+
+/// Set when a search finds a match; a search that finds nothing fails.
+static FOUND: AtomicBool = AtomicBool::new(false);
+
+/// Whether a search, if any, found a match.
+fn found_match(args: &Args) -> bool {
+    args.needle().is_none() || FOUND.load(Ordering::Relaxed)
+}
+
+/// Dump every input. Returns `false` if any input failed.
+fn run() -> io::Result<bool> {
     let args = Args::parse();
 
+    let mode = args.mode();
+
     // Determine line length based on mode
-    let line_length = args.line_length.unwrap_or(if args.hex {
-        DEFAULT_HEX_LINE_LENGTH
-    } else if args.binary {
-        DEFAULT_BINARY_LINE_LENGTH
-    } else {
-        DEFAULT_TEXT_LINE_LENGTH
-    });
+    let line_length = args
+        .line_length
+        .unwrap_or_else(|| mode.default_line_length());
 
     if line_length == 0 {
         eprintln!("Invalid length: 0");
         exit(1);
     }
 
-    // Set up input
-    let input = match &args.file {
-        Some(path) => InputSource::from_file(path)?,
-        None => InputSource::from_stdin(),
+    let group_size = args
+        .group
+        .unwrap_or(if args.xxd { XXD_GROUP_SIZE } else { 1 });
+
+    // Report a broken theme or rules file before any output, if colors
+    // are drawn at all
+    palette(&args)?;
+    highlights(&args)?;
+
+    // No files means stdin, as does "-"
+    let paths: Vec<Option<&str>> = if args.files.is_empty() {
+        vec![None]
+    } else {
+        args.files
+            .iter()
+            .map(|path| (path != "-").then_some(path.as_str()))
+            .collect()
     };
 
+    if args.follow && !matches!(paths[..], [Some(_)]) {
+        eprintln!("Follow mode needs a single file");
+        exit(1);
+    }
+
     // Set up output with large buffer
     let stdout = stdout();
     let lock = stdout.lock();
     let mut writer = BufWriter::with_capacity(WRITE_BUFFER_SIZE, lock);
 
+    if args.reverse {
+        let mut ok = true;
+        for &path in &paths {
+            let result = run_reverse(&args, mode, line_length, group_size, path, &mut writer);
+            ok &= check_input(result, path, &mut writer)?;
+        }
+        writer.flush()?;
+        return Ok(ok);
+    }
+
+    // Set up input, restricted to the requested byte range
+    let tail = match (args.tail, args.tail_lines) {
+        (Some(bytes), _) => Some(Tail::Bytes(bytes)),
+        (None, Some(_)) if matches!(mode, Mode::Text) => {
+            eprintln!("--tail-lines does not work in text mode: line breaks depend on every byte before them");
+            exit(1);
+        }
+        (None, Some(lines)) => Some(Tail::Lines {
+            lines,
+            line_bytes: mode.bytes_per_line(line_length),
+        }),
+        (None, None) => None,
+    };
+    let range = ByteRange {
+        skip: args.skip.unwrap_or(0),
+        count: args.count,
+        tail,
+    };
+
+    if let Some(pid) = args.pid {
+        let ok = dump_process(
+            &args,
+            pid,
+            mode,
+            line_length,
+            group_size,
+            &range,
+            &mut writer,
+        )?;
+        writer.flush()?;
+        return Ok(ok && found_match(&args));
+    }
+
+    let mut ok = true;
+    if args.concat {
+        // Open everything up front, then dump one continuous stream
+        let mut inputs = Vec::new();
+        for &path in &paths {
+            let result = open_input(&args, path, &ByteRange::default());
+            match result {
+                Ok(input) => {
+                    note_decompressed(&args, path, &input);
+                    inputs.push((display_path(path).to_string(), input))
+                }
+                Err(e) => ok &= check_input(Err(e), path, &mut writer)?,
+            }
+        }
+
+        let read_failed = Arc::new(AtomicBool::new(false));
+        let failed = Arc::clone(&read_failed);
+        let on_error = Box::new(move |name: &str, e: io::Error| {
+            failed.store(true, Ordering::Relaxed);
+            report_error(&format!("{}: {}", name, e));
+        });
+        let input = Input::concat(inputs, &range, on_error)?;
+        dump(
+            &args,
+            mode,
+            line_length,
+            group_size,
+            input,
+            paths[0],
+            &mut writer,
+        )?;
+        ok &= !read_failed.load(Ordering::Relaxed);
+    } else {
+        let headers = paths.len() > 1 && mode.shows_headers();
+        for (i, &path) in paths.iter().enumerate() {
+            let result = open_input(&args, path, &range).and_then(|input| {
+                if headers {
+                    write_header(path, &input, i > 0, &mut writer)?;
+                } else {
+                    note_decompressed(&args, path, &input);
+                }
+                dump(
+                    &args,
+                    mode,
+                    line_length,
+                    group_size,
+                    input,
+                    path,
+                    &mut writer,
+                )
+            });
+            ok &= check_input(result, path, &mut writer)?;
+        }
+    }
+
+    writer.flush()?;
+    Ok(ok && found_match(&args))
+}
+
+/// Report an input's error and carry on with the next input. Returns
+/// whether the input succeeded. Output errors stop everything.
+fn check_input<W: Write>(
+    result: io::Result<()>,
+    path: Option<&str>,
+    writer: &mut W,
+) -> io::Result<bool> {
+    match result {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Err(e),
+        Err(e) => {
+            // Keep the error after the output that preceded it
+            writer.flush()?;
+            report_error(&format!("{}: {}", display_path(path), e));
+            Ok(false)
+        }
+    }
+}
+
+fn display_path(path: Option<&str>) -> &str {
+    path.unwrap_or("-")
+}
+
+/// Tell on stderr that a compressed input is dumped decompressed, when
+/// neither `--decompress` nor a header line says so.
+fn note_decompressed(args: &Args, path: Option<&str>, input: &Input) {
+    if args.decompress.is_some() {
+        return;
+    }
+    if let InputSource::Compressed { compression, .. } = &input.source {
+        eprintln!(
+            "{}: {}-compressed, dumping the decompressed bytes (--decompress none keeps them)",
+            display_path(path),
+            compression.name()
+        );
+    }
+}
+
+/// Write the line introducing one of several inputs: its path, size and how it is read.
+fn write_header<W: Write>(
+    path: Option<&str>,
+    input: &Input,
+    separate: bool,
+    writer: &mut W,
+) -> io::Result<()> {
+    if separate {
+        writeln!(writer)?;
+    }
+
+    let size = path
+        .and_then(|path| fs::metadata(path).ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len());
+    let how = input.source.description();
+    match (size, &input.source) {
+        // The file's size is not the size of what is dumped
+        (Some(size), InputSource::Compressed { .. } | InputSource::Image(_)) => writeln!(
+            writer,
+            "==> {} ({}, {} bytes on disk) <==",
+            display_path(path),
+            how,
+            size
+        ),
+        (Some(size), _) => writeln!(
+            writer,
+            "==> {} ({} bytes, {}) <==",
+            display_path(path),
+            size,
+            how
+        ),
+        (None, _) => writeln!(writer, "==> {} ({}) <==", display_path(path), how),
+    }
+}
+
+/// Dump the mappings of a live process selected by `--region`, or list
+/// them all if no region is given.
+fn dump_process<W: Write>(
+    args: &Args,
+    pid: u32,
+    mode: Mode,
+    line_length: usize,
+    group_size: usize,
+    range: &ByteRange,
+    writer: &mut W,
+) -> io::Result<bool> {
+    let mappings = maps(pid)?;
+    let Some(region) = &args.region else {
+        for mapping in &mappings {
+            writeln!(
+                writer,
+                "{:x}-{:x} {} {:>12} {}",
+                mapping.start,
+                mapping.end,
+                mapping.perms,
+                mapping.end - mapping.start,
+                mapping.name
+            )?;
+        }
+        return Ok(true);
+    };
+
+    let selected = region.select(&mappings)?;
+    if selected.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no mapping of process {} matches the region", pid),
+        ));
+    }
+
+    let headers = mode.shows_headers();
+    let mut ok = true;
+    for (i, mapping) in selected.iter().enumerate() {
+        let name = match mapping.name.as_str() {
+            "" => "[anon]",
+            name => name,
+        };
+        let result = Input::memory(pid, mapping, range).and_then(|input| {
+            if headers {
+                if i > 0 {
+                    writeln!(writer)?;
+                }
+                let size = mapping.end - mapping.start;
+                match mapping.perms.as_str() {
+                    "" => writeln!(
+                        writer,
+                        "==> {} {:#x}..{:#x} ({} bytes) <==",
+                        name, mapping.start, mapping.end, size
+                    )?,
+                    perms => writeln!(
+                        writer,
+                        "==> {} {:#x}..{:#x} ({} bytes, {}) <==",
+                        name, mapping.start, mapping.end, size, perms
+                    )?,
+                }
+            }
+            dump(
+                args,
+                mode,
+                line_length,
+                group_size,
+                input,
+                Some(name),
+                writer,
+            )
+        });
+        ok &= check_input(result, Some(name), writer)?;
+    }
+    Ok(ok)
+}
+
+/// Dump one opened input in the selected mode.
+fn dump<W: Write>(
+    args: &Args,
+    mode: Mode,
+    line_length: usize,
+    group_size: usize,
+    input: Input,
+    path: Option<&str>,
+    writer: &mut W,
+) -> io::Result<()> {
+    // Size the offset column for the largest offset we will print. Offsets
+    // of a stream whose length is unknown wrap around instead.
+    let start_offset = args.base_address.wrapping_add(input.start);
+    let last_offset = args
+        .base_address
+        .checked_add(input.start)
+        .and_then(|start| start.checked_add(input.len.unwrap_or(0)))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "base address {:#x} puts offsets past {:#x}",
+                    args.base_address,
+                    u64::MAX
+                ),
+            )
+        })?;
+
+    let mut offset_width = args.offset_format.width(last_offset);
+    if args.xxd {
+        offset_width = offset_width.max(XXD_OFFSET_WIDTH);
+    }
+
+    let ansi = ansi(args);
+
     // Create config
     let config = DumpConfig {
         bytes_per_line: line_length,
-        color_enabled: args.color,
+        ansi,
+        color_enabled: ansi && args.color.is_some(),
+        palette: palette(args)?,
+        highlights: highlights(args)?,
         space_highlight: args.space,
+        ascii_column: args.canonical || args.xxd,
+        start_offset,
+        offset_format: args.offset_format,
+        offset_width,
+        byte_format: match mode {
+            Mode::Bytes(format) => format,
+            _ => ByteFormat::Hex,
+        },
+        layout: if args.xxd {
+            HexLayout::Xxd
+        } else {
+            HexLayout::Bred
+        },
+        group_size,
+        endian: args.endian,
+        // xxd does not squeeze, and its layout must stay byte-compatible.
+        // A followed file shows each line as it arrives, repeated or not.
+        squeeze: matches!(mode, Mode::Bytes(_)) && !args.xxd && !args.no_squeezing && !args.follow,
     };
 
-    // Dispatch to appropriate mode
-    match (args.hex, args.binary) {
-        (true, _) => process_hex(input, config, &mut writer)?,
-        (false, true) => process_binary(input, config, &mut writer)?,
-        (false, false) => process_text(input, config, &mut writer)?,
+    if let Some(needle) = args.needle() {
+        return search(args, mode, config, needle, input, writer);
     }
 
-    writer.flush()?;
-    Ok(())
-}
+    // Mapped files are formatted on several threads; streams arrive in
+    // pieces too small to be worth splitting
+    let threads = match input.source {
+        InputSource::Mmap(_) => args.threads.map_or_else(
+            || thread::available_parallelism().map_or(1, |n| n.get()),
+            |n| n as usize,
+        ),
+        _ => 1,
+    };
 
-fn process_hex<W: Write>(input: InputSource, config: DumpConfig, writer: &mut W) -> io::Result<()> {
-    let mut dumper = HexDumper::new(config);
+    // Dispatch to appropriate mode
+    let source = input.source;
+    match mode {
+        Mode::Bytes(_) => process_input(
+            source,
+            config,
+            |config| {
+                HighlightDumper::new(config, ParallelDumper::new(HexDumper::new(config), threads))
+            },
+            writer,
+        ),
+        Mode::Plain => process_input(
+            source,
+            config,
+            |config| HighlightDumper::new(config, PlainHexDumper::new(config)),
+            writer,
+        ),
+        Mode::Binary => process_input(
+            source,
+            config,
+            |config| {
+                HighlightDumper::new(
+                    config,
+                    ParallelDumper::new(BinaryDumper::new(config), threads),
+                )
+            },
+            writer,
+        ),
+        Mode::Text => process_input(
+            source,
+            config,
+            |config| {
+                HighlightDumper::new(
+                    config,
+                    ParallelDumper::new(TextDumper::new(config), threads),
+                )
+            },
+            writer,
+        ),
+        Mode::Typed(value_type) => process_input(
+            source,
+            config,
+            |config| HighlightDumper::new(config, TypedDumper::new(config, value_type)),
+            writer,
+        ),
+        Mode::Include(language) => {
+            let name = match &args.name {
+                Some(name) => name.clone(),
+                None => language.identifier(path.unwrap_or("data")),
+            };
+            process_input(
+                source,
+                config,
+                |config| IncludeDumper::new(config, language, args.string, name.clone()),
+                writer,
+            )
+        }
+        Mode::Encode(encoding) => process_input(
+            source,
+            config,
+            |config| EncodeDumper::new(config, encoding),
+            writer,
+        ),
+        Mode::Records(RecordFormat::Ihex) => {
+            if line_length > MAX_RECORD_BYTES {
+                eprintln!(
+                    "Invalid length for Intel HEX: {} (at most {})",
+                    line_length, MAX_RECORD_BYTES
+                );
+                exit(1);
+            }
+            process_input(source, config, IntelHexDumper::new, writer)
+        }
+        Mode::Records(RecordFormat::Srec) => {
+            let srec_type = args.srec_type.unwrap_or(match input.len {
+                Some(_) => SrecType::for_address(last_offset.saturating_sub(1)),
+                None => SrecType::S37,
+            });
+            if line_length > srec_type.max_data_bytes() {
+                eprintln!(
+                    "Invalid length for S-records: {} (at most {})",
+                    line_length,
+                    srec_type.max_data_bytes()
+                );
+                exit(1);
+            }
+            process_input(
+                source,
+                config,
+                |config| SrecDumper::new(config, srec_type),
+                writer,
+            )
+        }
+    }
+}
 
-    match input {
+/// Search one opened input, printing each match, the lines around the
+/// matches, or how many there are.
+fn search<W: Write>(
+    args: &Args,
+    mode: Mode,
+    config: DumpConfig,
+    needle: &Needle,
+    input: Input,
+    writer: &mut W,
+) -> io::Result<()> {
+    let breaks = match mode {
+        Mode::Text => LineBreaks::Text {
+            width: config.bytes_per_line,
+        },
+        _ => LineBreaks::Every(mode.bytes_per_line(config.bytes_per_line)),
+    };
+    let context = args.context.map(|lines| Context { lines, breaks });
+    let limit = args.first.then_some(1);
+    let mut searcher = Searcher::new(needle, limit, context, config.start_offset);
+    let mut printer = MatchPrinter {
+        args,
+        mode,
+        config,
+        writer,
+        dumps: 0,
+    };
+    match input.source {
         InputSource::Mmap(mmap) => {
-            // Zero-copy path for memory-mapped files
-            dumper.process(mmap.as_slice(), writer)?;
+            search_slice(
+                &mut searcher,
+                mmap.as_slice(),
+                config.start_offset,
+                &mut printer,
+            )?;
+            mmap.check()?;
         }
-        InputSource::Stream(mut reader) => {
-            // Streaming path for stdin/small files
-            let mut buffer = vec![0u8; READ_BUFFER_SIZE];
-            loop {
-                let n = reader.read(&mut buffer)?;
-                if n == 0 {
-                    break;
+        InputSource::Memory(memory) => {
+            search_memory(&mut searcher, memory, config.start_offset, &mut printer)?
+        }
+        InputSource::Image(image) => {
+            // No match or context spans a gap between records
+            let mut offset = config.start_offset;
+            image.for_each_segment(|segment| {
+                match segment {
+                    Segment::Data(data) => {
+                        if !searcher.done() {
+                            searcher.search(data, offset, true, &mut printer)?;
+                        }
+                        offset = offset.wrapping_add(data.len() as u64);
+                    }
+                    Segment::Hole(len) => {
+                        offset = offset.wrapping_add(len);
+                        searcher.resume_at(offset);
+                    }
                 }
-                dumper.process(&buffer[..n], writer)?;
-            }
+                Ok(())
+            })?;
         }
+        source => search_reader(
+            &mut searcher,
+            source.into_reader(),
+            config.start_offset,
+            &mut printer,
+        )?,
     }
 
-    dumper.finish(writer)?;
+    if searcher.found() > 0 {
+        FOUND.store(true, Ordering::Relaxed);
+    }
+    if args.count_matches {
+        writeln!(printer.writer, "{}", searcher.found())?;
+    }
     Ok(())
 }
 
-fn process_binary<W: Write>(
-    input: InputSource,
+/// Prints what a search finds: a line per match, or with `--context` a
+/// dump of the lines around them in the selected mode.
+struct MatchPrinter<'a, W> {
+    args: &'a Args,
+    mode: Mode,
+    config: DumpConfig,
+    writer: &'a mut W,
+    /// Context dumps printed so far, which are separated by `--` lines.
+    dumps: u64,
+}
+
+impl<W: Write> Sink for MatchPrinter<'_, W> {
+    fn found(&mut self, offset: u64, bytes: &[u8]) -> io::Result<()> {
+        if self.args.count_matches || self.args.context.is_some() {
+            return Ok(());
+        }
+        write_match(&self.config, offset, bytes, self.writer)
+    }
+
+    fn context(&mut self, data: &[u8], offset: u64, spans: &[(u64, u64)]) -> io::Result<()> {
+        if self.dumps > 0 {
+            writeln!(self.writer, "--")?;
+        }
+        self.dumps += 1;
+
+        let mut marks = vec![0u8; data.len()];
+        if let Some(mark) = self.config.highlights.match_mark() {
+            for &(start, end) in spans {
+                marks[(start - offset) as usize..(end - offset) as usize].fill(mark);
+            }
+        }
+        let mut config = self.config;
+        config.start_offset = offset;
+        let writer = &mut *self.writer;
+        match self.mode {
+            Mode::Bytes(_) => dump_marked(HexDumper::new(config), config, data, &marks, writer),
+            Mode::Plain => dump_marked(PlainHexDumper::new(config), config, data, &marks, writer),
+            Mode::Binary => dump_marked(BinaryDumper::new(config), config, data, &marks, writer),
+            Mode::Text => dump_marked(TextDumper::new(config), config, data, &marks, writer),
+            Mode::Typed(value_type) => dump_marked(
+                TypedDumper::new(config, value_type),
+                config,
+                data,
+                &marks,
+                writer,
+            ),
+            // Ruled out by the arguments
+            Mode::Include(_) | Mode::Encode(_) | Mode::Records(_) => unreachable!(),
+        }
+    }
+}
+
+/// Dump `data` with its highlight `marks` and any highlight rules.
+fn dump_marked<D: Dumper, W: Write>(
+    dumper: D,
     config: DumpConfig,
+    data: &[u8],
+    marks: &[u8],
     writer: &mut W,
 ) -> io::Result<()> {
-    let mut dumper = BinaryDumper::new(config);
+    let mut dumper = HighlightDumper::new(config, dumper);
+    dumper.process_marked(data, marks, writer)?;
+    dumper.finish(writer)
+}
 
-    match input {
+fn run_reverse<W: Write>(
+    args: &Args,
+    mode: Mode,
+    line_length: usize,
+    group_size: usize,
+    path: Option<&str>,
+    writer: &mut W,
+) -> io::Result<()> {
+    let kind = match mode {
+        Mode::Bytes(format) if args.xxd => DumpKind::Xxd {
+            hex_width: (args.endian == Endian::Little)
+                .then(|| hex_column_width(line_length, group_size, format, false)),
+        },
+        Mode::Bytes(format) => DumpKind::Bytes(format),
+        Mode::Plain => DumpKind::Plain,
+        Mode::Binary => DumpKind::Binary,
+        Mode::Text if args.space => {
+            eprintln!("Reverse mode does not support --space: `_` stands for both a space and an underscore");
+            exit(1);
+        }
+        Mode::Text => DumpKind::Text,
+        Mode::Typed(_) => {
+            eprintln!("Reverse mode does not support --type");
+            exit(1);
+        }
+        Mode::Include(_) => {
+            eprintln!("Reverse mode does not support --include");
+            exit(1);
+        }
+        Mode::Encode(_) | Mode::Records(_) => {
+            // Reversing an encoding or record file is loading it
+            let range = ByteRange::default();
+            let input = match mode {
+                Mode::Records(format) => Input::imported(path, format, &range)?,
+                Mode::Encode(encoding) => Input::decoded(path, encoding, &range)?,
+                _ => unreachable!(),
+            };
+            return copy_input(input.source, writer);
+        }
+    };
+
+    let input = open_input(args, path, &ByteRange::default())?;
+
+    let mut reverser = Reverser::new(ReverseConfig {
+        kind,
+        endian: args.endian,
+        offset_format: args.offset_format,
+        base_address: args.base_address,
+    });
+    match input.source {
         InputSource::Mmap(mmap) => {
-            dumper.process(mmap.as_slice(), writer)?;
+            let result = reverser.run(mmap.as_slice(), writer);
+            // A parse error may be the zeros of a truncated file
+            mmap.check()?;
+            result
         }
-        InputSource::Stream(mut reader) => {
-            let mut buffer = vec![0u8; READ_BUFFER_SIZE];
-            loop {
-                let n = reader.read(&mut buffer)?;
-                if n == 0 {
-                    break;
-                }
-                dumper.process(&buffer[..n], writer)?;
-            }
+        InputSource::Stream(reader) | InputSource::Compressed { reader, .. } => {
+            reverser.run(reader, writer)
         }
+        InputSource::Follow(follower) => reverser.run(BufReader::new(follower), writer),
+        InputSource::Memory(memory) => reverser.run(BufReader::new(memory), writer),
+        InputSource::Image(image) => reverser.run(BufReader::new(image), writer),
     }
+}
 
-    dumper.finish(writer)?;
-    Ok(())
+/// Open a file, or stdin if `path` is `None`, decoding it first if asked.
+fn open_input(args: &Args, path: Option<&str>, range: &ByteRange) -> io::Result<Input> {
+    if let (true, Some(path)) = (args.follow, path) {
+        return Input::follow(path, range);
+    }
+    if let Some(format) = args.import {
+        return Input::imported(path, format, range);
+    }
+    match (args.decode, path) {
+        (Some(encoding), path) => Input::decoded(path, encoding, range),
+        (None, Some(path)) => Input::from_file(path, range, args.decompress()),
+        (None, None) => Input::from_stdin(range, args.decompress()),
+    }
+}
+
+/// Write the input bytes unchanged.
+fn copy_input<W: Write>(input: InputSource, writer: &mut W) -> io::Result<()> {
+    match input {
+        InputSource::Mmap(mmap) => mmap.for_each_segment(|segment| match segment {
+            Segment::Data(data) => writer.write_all(data),
+            Segment::Hole(len) => io::copy(&mut io::repeat(0).take(len), writer).map(|_| ()),
+        }),
+        InputSource::Stream(mut reader) | InputSource::Compressed { mut reader, .. } => {
+            io::copy(&mut reader, writer).map(|_| ())
+        }
+        InputSource::Follow(mut follower) => io::copy(&mut follower, writer).map(|_| ()),
+        InputSource::Memory(mut memory) => io::copy(&mut memory, writer).map(|_| ()),
+        // A flat binary image has zeros between the records
+        InputSource::Image(image) => image.for_each_segment(|segment| match segment {
+            Segment::Data(data) => writer.write_all(data),
+            Segment::Hole(len) => io::copy(&mut io::repeat(0).take(len), writer).map(|_| ()),
+        }),
+    }
 }
 
-fn process_text<W: Write>(
+/// Parse a `--group` size, which must be a whole machine word.
+fn parse_group(s: &str) -> Result<usize, String> {
+    match s {
+        "1" => Ok(1),
+        "2" => Ok(2),
+        "4" => Ok(4),
+        "8" => Ok(8),
+        _ => Err(format!("invalid group size: {} (expected 1, 2, 4 or 8)", s)),
+    }
+}
+
+/// Run a dumper made by `make` over the whole input. A followed file that
+/// is truncated or replaced gets a fresh dumper, so offsets start over.
+fn process_input<D, F, W>(
     input: InputSource,
-    config: DumpConfig,
+    mut config: DumpConfig,
+    make: F,
     writer: &mut W,
-) -> io::Result<()> {
-    let mut dumper = TextDumper::new(config);
-
+) -> io::Result<()>
+where
+    D: Dumper,
+    F: Fn(DumpConfig) -> D,
+    W: Write,
+{
+    let mut dumper = make(config);
     match input {
         InputSource::Mmap(mmap) => {
-            dumper.process(mmap.as_slice(), writer)?;
+            // Zero-copy path for memory-mapped files; holes are never read
+            let result = mmap.for_each_segment(|segment| match segment {
+                Segment::Data(data) => dumper.process(data, writer),
+                Segment::Hole(len) => dumper.hole(len, writer),
+            });
+            if let Err(e) = result {
+                // Show what was read before the file was cut short
+                dumper.finish(writer)?;
+                return Err(e);
+            }
+        }
+        InputSource::Stream(reader) => {
+            // Streaming path for stdin/small files
+            process_stream(reader, &mut dumper, writer, None)?;
         }
-        InputSource::Stream(mut reader) => {
+        InputSource::Compressed {
+            reader,
+            compression,
+        } => {
+            process_stream(reader, &mut dumper, writer, Some(compression))?;
+        }
+        InputSource::Image(image) => {
+            // Gaps between records are passed on like holes
+            image.for_each_segment(|segment| match segment {
+                Segment::Data(data) => dumper.process(data, writer),
+                Segment::Hole(len) => dumper.hole(len, writer),
+            })?;
+        }
+        InputSource::Memory(mut memory) => {
             let mut buffer = vec![0u8; READ_BUFFER_SIZE];
-            loop {
-                let n = reader.read(&mut buffer)?;
-                if n == 0 {
-                    break;
+            while let Some(chunk) = memory.next(&mut buffer)? {
+                match chunk {
+                    MemoryChunk::Data(n) => dumper.process(&buffer[..n], writer)?,
+                    MemoryChunk::Unreadable(len) => dumper.unreadable(len, writer)?,
                 }
-                dumper.process(&buffer[..n], writer)?;
+            }
+        }
+        InputSource::Follow(mut follower) => {
+            let base_address = config.start_offset.wrapping_sub(follower.position());
+            let mut buffer = vec![0u8; READ_BUFFER_SIZE];
+            loop {
+                // Show what has arrived so far before waiting for more
+                let event = follower.next(&mut buffer, || {
+                    dumper.flush_line(writer)?;
+                    writer.flush()
+                })?;
+                let what = match event {
+                    FollowEvent::Data(n) => {
+                        dumper.process(&buffer[..n], writer)?;
+                        continue;
+                    }
+                    FollowEvent::Truncated => "file truncated",
+                    FollowEvent::Rotated => "file replaced",
+                };
+
+                dumper.finish(writer)?;
+                writer.flush()?;
+                eprintln!("{}: {}, dumping from the start", follower.path(), what);
+                config.start_offset = base_address;
+                dumper = make(config);
             }
         }
     }
@@ -177,3 +1243,27 @@ fn process_text<W: Write>(
     dumper.finish(writer)?;
     Ok(())
 }
+
+fn process_stream<R: Read, D: Dumper, W: Write>(
+    mut reader: R,
+    dumper: &mut D,
+    writer: &mut W,
+    compression: Option<Compression>,
+) -> io::Result<()> {
+    let mut buffer = vec![0u8; READ_BUFFER_SIZE];
+    loop {
+        let n = reader.read(&mut buffer).map_err(|e| match compression {
+            // Name the format so corrupt archives are not mistaken for I/O errors
+            Some(compression) => io::Error::new(
+                e.kind(),
+                format!("decompressing {} input: {}", compression.name(), e),
+            ),
+            None => e,
+        })?;
+        if n == 0 {
+            break;
+        }
+        dumper.process(&buffer[..n], writer)?;
+    }
+    Ok(())
+}
@@ -1,17 +1,21 @@
 //! Pre-allocated output buffer for zero-allocation formatting.
 
-/// Fixed-size buffer for building output lines.
-/// Sized for worst case: offset + pipe + bytes with colors + pipe + newline.
+/// Initial capacity, enough for a default line with colors.
+const INITIAL_CAPACITY: usize = 1024;
+
+/// Reusable buffer for building output lines. It keeps its capacity across
+/// lines, so it only allocates when a line is longer than any before it
+/// (long `--length` values, truecolor escapes).
 pub struct LineBuffer {
-    data: [u8; 1024],
+    data: Vec<u8>,
     pos: usize,
 }
 
 impl LineBuffer {
     #[inline(always)]
-    pub const fn new() -> Self {
+    pub fn new() -> Self {
         Self {
-            data: [0u8; 1024],
+            data: vec![0u8; INITIAL_CAPACITY],
             pos: 0,
         }
     }
@@ -23,16 +27,28 @@ impl LineBuffer {
 
     #[inline(always)]
     pub fn push(&mut self, b: u8) {
+        if self.pos == self.data.len() {
+            self.grow(1);
+        }
         self.data[self.pos] = b;
         self.pos += 1;
     }
 
     #[inline(always)]
     pub fn extend(&mut self, slice: &[u8]) {
+        if self.pos + slice.len() > self.data.len() {
+            self.grow(slice.len());
+        }
         self.data[self.pos..self.pos + slice.len()].copy_from_slice(slice);
         self.pos += slice.len();
     }
 
+    #[cold]
+    fn grow(&mut self, additional: usize) {
+        let len = (self.pos + additional).max(2 * self.data.len());
+        self.data.resize(len, 0);
+    }
+
     #[inline(always)]
     pub fn as_slice(&self) -> &[u8] {
         &self.data[..self.pos]
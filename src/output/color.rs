@@ -1,7 +1,36 @@
-//! ANSI color code constants.
+//! ANSI reset code and the `--color` policy. Colors come from the theme.
+
+use clap::ValueEnum;
+use std::env;
 
 pub const RESET: &[u8] = b"\x1b[0m";
-pub const GRAY: &[u8] = b"\x1b[90m";
-pub const RED: &[u8] = b"\x1b[91m";
-pub const GREEN: &[u8] = b"\x1b[32m";
-pub const ORANGE: &[u8] = b"\x1b[38;5;130m";
+
+/// When to write color escape codes.
+#[derive(Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color output to a terminal, unless `NO_COLOR` is set or `CLICOLOR_FORCE` forces it
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to color a stream that is a terminal if `is_terminal`.
+    pub fn enabled(self, is_terminal: bool) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                // https://no-color.org and https://bixense.com/clicolors
+                if env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+                    false
+                } else if env::var_os("CLICOLOR_FORCE").is_some_and(|v| !v.is_empty() && v != "0") {
+                    true
+                } else {
+                    is_terminal && env::var_os("TERM").is_none_or(|term| term != "dumb")
+                }
+            }
+        }
+    }
+}
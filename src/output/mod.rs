@@ -2,3 +2,5 @@
 
 pub mod buffer;
 pub mod color;
+pub mod offset;
+pub mod theme;
@@ -0,0 +1,66 @@
+//! Offset column formatting.
+
+use super::buffer::LineBuffer;
+use clap::ValueEnum;
+
+/// Minimum number of digits in the offset column.
+pub const MIN_OFFSET_WIDTH: usize = 7;
+
+/// Radix used to print the offset column.
+#[derive(Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OffsetFormat {
+    #[default]
+    Hex,
+    Dec,
+    Oct,
+}
+
+impl OffsetFormat {
+    #[inline(always)]
+    const fn radix(self) -> u64 {
+        match self {
+            OffsetFormat::Hex => 16,
+            OffsetFormat::Dec => 10,
+            OffsetFormat::Oct => 8,
+        }
+    }
+
+    /// Column width needed to print every offset up to `max_offset`.
+    pub fn width(self, max_offset: u64) -> usize {
+        let radix = self.radix();
+        let mut digits = 1;
+        let mut n = max_offset / radix;
+        while n > 0 {
+            digits += 1;
+            n /= radix;
+        }
+        digits.max(MIN_OFFSET_WIDTH)
+    }
+}
+
+/// Push `offset` zero-padded to `width` digits. Offsets that need more
+/// digits than `width` are printed in full rather than truncated.
+#[inline(always)]
+pub fn push_offset(buf: &mut LineBuffer, offset: u64, format: OffsetFormat, width: usize) {
+    const DIGITS: &[u8] = b"0123456789abcdef";
+    let radix = format.radix();
+
+    // u64::MAX in octal is 22 digits, the widest case.
+    let mut digits = [b'0'; 22];
+    let mut pos = digits.len();
+    let mut n = offset;
+    loop {
+        pos -= 1;
+        digits[pos] = DIGITS[(n % radix) as usize];
+        n /= radix;
+        if n == 0 {
+            break;
+        }
+    }
+
+    let len = digits.len() - pos;
+    for _ in len..width {
+        buf.push(b'0');
+    }
+    buf.extend(&digits[pos..]);
+}
@@ -0,0 +1,572 @@
+//! Color themes: the built-in dark, light and high-contrast themes and
+//! user themes from the config directory, turned into escape codes for the
+//! color depth of the terminal.
+//!
+//! Theme files hold `role = style` lines, where a style is `none` or any of
+//! `bold`, `underline`, a foreground color and `on` a background color.
+//! Colors are names (`red`, `bright-black`, `gray`), 256-color indexes or
+//! `#rrggbb`. `base = NAME` starts from another theme; roles that are not
+//! set keep its colors.
+
+use crate::hex::{ByteClass, BYTE_CLASS, BYTE_GRADIENT};
+use clap::ValueEnum;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Themes a theme file can build on through `base`, counting itself.
+const MAX_BASE_DEPTH: usize = 8;
+
+/// How bytes are colored.
+#[derive(Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Coloring {
+    /// By class: null, control, printable and extended, in theme colors
+    #[default]
+    Class,
+    /// Every byte value in its own color of a dark-to-bright gradient
+    Gradient,
+}
+
+/// Colors the terminal can show.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+impl ColorDepth {
+    /// Depth advertised by `COLORTERM` and `TERM`.
+    pub fn detect() -> Self {
+        let colorterm = env::var("COLORTERM").unwrap_or_default();
+        let term = env::var("TERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" || term.ends_with("-direct") {
+            ColorDepth::TrueColor
+        } else if term.contains("256color") {
+            ColorDepth::Ansi256
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+}
+
+/// A color as written in a theme.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Color {
+    /// One of the 16 standard colors, 8-15 being the bright ones.
+    Ansi(u8),
+    /// An entry of the 256-color palette.
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+/// Usual values of the 16 standard colors (xterm's defaults).
+const ANSI_RGB: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// Channel levels of the 6x6x6 color cube at indexes 16-231.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+const COLOR_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+impl Color {
+    fn parse(s: &str) -> Result<Color, String> {
+        if let Some(hex) = s.strip_prefix('#') {
+            let channel = |i: usize| {
+                hex.get(i..i + 2)
+                    .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+            };
+            return match (hex.len(), channel(0), channel(2), channel(4)) {
+                (6, Some(r), Some(g), Some(b)) => Ok(Color::Rgb(r, g, b)),
+                _ => Err(format!("invalid color: {} (expected #rrggbb)", s)),
+            };
+        }
+        if let Ok(index) = s.parse::<u8>() {
+            return Ok(Color::Indexed(index));
+        }
+
+        let (bright, name) = match s.strip_prefix("bright-") {
+            Some(name) => (8, name),
+            None if s == "gray" || s == "grey" => (8, "black"),
+            None => (0, s),
+        };
+        COLOR_NAMES
+            .iter()
+            .position(|&known| known == name)
+            .map(|n| Color::Ansi(bright + n as u8))
+            .ok_or_else(|| format!("unknown color: {}", s))
+    }
+
+    fn rgb(self) -> (u8, u8, u8) {
+        match self {
+            Color::Ansi(n) => ANSI_RGB[n as usize],
+            Color::Indexed(n) if n < 16 => ANSI_RGB[n as usize],
+            Color::Indexed(n) if n < 232 => {
+                let n = (n - 16) as usize;
+                (
+                    CUBE_LEVELS[n / 36],
+                    CUBE_LEVELS[n / 6 % 6],
+                    CUBE_LEVELS[n % 6],
+                )
+            }
+            Color::Indexed(n) => {
+                let level = 8 + 10 * (n - 232);
+                (level, level, level)
+            }
+            Color::Rgb(r, g, b) => (r, g, b),
+        }
+    }
+
+    /// The closest color the terminal can show.
+    fn downgrade(self, depth: ColorDepth) -> Color {
+        match (self, depth) {
+            (_, ColorDepth::TrueColor) | (Color::Ansi(_), _) => self,
+            (Color::Indexed(_), ColorDepth::Ansi256) => self,
+            (Color::Rgb(r, g, b), ColorDepth::Ansi256) => Color::Indexed(nearest_256((r, g, b))),
+            (Color::Indexed(n), ColorDepth::Ansi16) if n < 16 => Color::Ansi(n),
+            (_, ColorDepth::Ansi16) => Color::Ansi(nearest_16(self.rgb())),
+        }
+    }
+
+    /// SGR parameters selecting this color.
+    fn sgr(self, background: bool) -> String {
+        let base = if background { 40 } else { 30 };
+        match self {
+            Color::Ansi(n) if n < 8 => (base + n).to_string(),
+            Color::Ansi(n) => (base + 60 + n - 8).to_string(),
+            Color::Indexed(n) => format!("{};5;{}", base + 8, n),
+            Color::Rgb(r, g, b) => format!("{};2;{};{};{}", base + 8, r, g, b),
+        }
+    }
+}
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+fn nearest_16(rgb: (u8, u8, u8)) -> u8 {
+    (0..16)
+        .min_by_key(|&n| distance(ANSI_RGB[n as usize], rgb))
+        .unwrap_or(0)
+}
+
+/// Closest entry of the color cube or the gray ramp.
+fn nearest_256((r, g, b): (u8, u8, u8)) -> u8 {
+    let level = |c: u8| {
+        (0..6)
+            .min_by_key(|&i| (CUBE_LEVELS[i as usize] as i32 - c as i32).abs())
+            .unwrap_or(0)
+    };
+    let cube = 16 + 36 * level(r) + 6 * level(g) + level(b);
+
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let gray = 232 + (average.saturating_sub(3) / 10).min(23) as u8;
+
+    let rgb = (r, g, b);
+    if distance(Color::Indexed(gray).rgb(), rgb) < distance(Color::Indexed(cube).rgb(), rgb) {
+        gray
+    } else {
+        cube
+    }
+}
+
+/// How one part of the output is drawn.
+#[derive(Clone, Copy, Default)]
+pub struct Style {
+    fg: Option<Color>,
+    /// Foreground on 16-color terminals, where the closest standard color
+    /// to `fg` would look like another role's.
+    fg16: Option<Color>,
+    bg: Option<Color>,
+    bold: bool,
+    underline: bool,
+}
+
+impl Style {
+    const NONE: Style = Style {
+        fg: None,
+        fg16: None,
+        bg: None,
+        bold: false,
+        underline: false,
+    };
+
+    const fn fg(color: Color) -> Style {
+        Style {
+            fg: Some(color),
+            ..Style::NONE
+        }
+    }
+
+    const fn or_16(self, color: Color) -> Style {
+        Style {
+            fg16: Some(color),
+            ..self
+        }
+    }
+
+    const fn bold(self) -> Style {
+        Style { bold: true, ..self }
+    }
+
+    const fn on(self, color: Color) -> Style {
+        Style {
+            bg: Some(color),
+            ..self
+        }
+    }
+
+    /// Parse `none` or any of `bold`, `underline`, a color and `on` a
+    /// background color.
+    pub fn parse(s: &str) -> Result<Style, String> {
+        let mut style = Style::NONE;
+        let mut words = s.split_whitespace();
+        while let Some(word) = words.next() {
+            match word {
+                "none" => {}
+                "bold" => style.bold = true,
+                "underline" => style.underline = true,
+                "on" => {
+                    let color = words.next().ok_or("missing color after `on`")?;
+                    style.bg = Some(Color::parse(color)?);
+                }
+                color => style.fg = Some(Color::parse(color)?),
+            }
+        }
+        Ok(style)
+    }
+
+    /// Escape code that starts this style, empty for no style.
+    pub fn escape(self, depth: ColorDepth) -> Vec<u8> {
+        let mut params = Vec::new();
+        if self.bold {
+            params.push("1".to_string());
+        }
+        if self.underline {
+            params.push("4".to_string());
+        }
+        let fg = match (depth, self.fg16) {
+            (ColorDepth::Ansi16, Some(fg16)) => Some(fg16),
+            _ => self.fg,
+        };
+        if let Some(fg) = fg {
+            params.push(fg.downgrade(depth).sgr(false));
+        }
+        if let Some(bg) = self.bg {
+            params.push(bg.downgrade(depth).sgr(true));
+        }
+
+        if params.is_empty() {
+            Vec::new()
+        } else {
+            format!("\x1b[{}m", params.join(";")).into_bytes()
+        }
+    }
+}
+
+/// Colors for each part of the output.
+#[derive(Clone, Copy)]
+pub struct Theme {
+    null: Style,
+    control: Style,
+    printable: Style,
+    extended: Style,
+    /// Offset column, and hole and unreadable markers.
+    offset: Style,
+    /// The `|` around byte columns.
+    separator: Style,
+    /// Spaces with `--space`.
+    highlight: Style,
+}
+
+const DARK: Theme = Theme {
+    null: Style::fg(Color::Ansi(8)),
+    control: Style::fg(Color::Ansi(9)),
+    printable: Style::NONE,
+    // Orange would become red, the color of control bytes
+    extended: Style::fg(Color::Indexed(130)).or_16(Color::Ansi(3)),
+    offset: Style::fg(Color::Ansi(8)),
+    separator: Style::NONE,
+    highlight: Style::fg(Color::Ansi(2)),
+};
+
+const LIGHT: Theme = Theme {
+    null: Style::fg(Color::Indexed(247)),
+    control: Style::fg(Color::Ansi(1)),
+    printable: Style::NONE,
+    extended: Style::fg(Color::Indexed(166)).or_16(Color::Ansi(3)),
+    offset: Style::fg(Color::Indexed(244)),
+    separator: Style::NONE,
+    highlight: Style::fg(Color::Indexed(28)),
+};
+
+const HIGH_CONTRAST: Theme = Theme {
+    null: Style::fg(Color::Ansi(12)),
+    control: Style::fg(Color::Ansi(9)).bold(),
+    printable: Style::fg(Color::Ansi(15)),
+    extended: Style::fg(Color::Ansi(11)).bold(),
+    offset: Style::fg(Color::Ansi(14)),
+    separator: Style::fg(Color::Ansi(7)),
+    highlight: Style::fg(Color::Ansi(0)).on(Color::Ansi(10)),
+};
+
+const BUILT_IN: [(&str, Theme); 3] = [
+    ("dark", DARK),
+    ("light", LIGHT),
+    ("high-contrast", HIGH_CONTRAST),
+];
+
+impl Theme {
+    /// Load the theme `name`, or the one named in the config file
+    /// (`theme = NAME`), or the dark theme.
+    pub fn load(name: Option<&str>) -> io::Result<Theme> {
+        let dir = config_dir();
+        let configured = match (name, &dir) {
+            (None, Some(dir)) => read_config(&dir.join("config"))?,
+            _ => None,
+        };
+        let name = name.or(configured.as_deref()).unwrap_or("dark");
+        Theme::named(name, dir.as_deref(), MAX_BASE_DEPTH)
+    }
+
+    /// A path to a theme file, a user theme `themes/NAME.theme` in the
+    /// config directory, or a built-in theme, in that order.
+    fn named(name: &str, dir: Option<&Path>, depth: usize) -> io::Result<Theme> {
+        if depth == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("theme {}: too many nested base themes", name),
+            ));
+        }
+        if name.contains('/') {
+            return Theme::from_file(Path::new(name), dir, depth);
+        }
+        if let Some(dir) = dir {
+            let path = dir.join("themes").join(format!("{}.theme", name));
+            if path.is_file() {
+                return Theme::from_file(&path, Some(dir), depth);
+            }
+        }
+
+        BUILT_IN
+            .iter()
+            .find(|(known, _)| *known == name)
+            .map(|&(_, theme)| theme)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!(
+                        "unknown theme: {} (built in: dark, light, high-contrast; \
+                         user themes go in ~/.config/bred/themes/{}.theme)",
+                        name, name
+                    ),
+                )
+            })
+    }
+
+    fn from_file(path: &Path, dir: Option<&Path>, depth: usize) -> io::Result<Theme> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+        let entries = entries(&text, path)?;
+
+        let mut theme = match entries.iter().find(|(_, key, _)| *key == "base") {
+            Some((_, _, base)) => Theme::named(base, dir, depth - 1)?,
+            None => DARK,
+        };
+        for (line, key, value) in entries {
+            let invalid = |message: String| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}:{}: {}", path.display(), line, message),
+                )
+            };
+            let role = match key {
+                "base" => continue,
+                "null" => &mut theme.null,
+                "control" => &mut theme.control,
+                "printable" => &mut theme.printable,
+                "extended" => &mut theme.extended,
+                "offset" => &mut theme.offset,
+                "separator" => &mut theme.separator,
+                "highlight" => &mut theme.highlight,
+                _ => {
+                    return Err(invalid(format!(
+                        "unknown key: {} (expected base, null, control, printable, \
+                         extended, offset, separator or highlight)",
+                        key
+                    )))
+                }
+            };
+            *role = Style::parse(value).map_err(invalid)?;
+        }
+        Ok(theme)
+    }
+
+    /// Escape codes for this theme at `depth`.
+    pub fn palette(&self, depth: ColorDepth, coloring: Coloring) -> Palette {
+        let classes = [
+            self.null.escape(depth),
+            self.control.escape(depth),
+            self.printable.escape(depth),
+            self.extended.escape(depth),
+        ];
+        let bytes = (0..256)
+            .map(|byte| match coloring {
+                Coloring::Class => classes[BYTE_CLASS[byte] as usize].clone(),
+                Coloring::Gradient => {
+                    let (r, g, b) = BYTE_GRADIENT[byte];
+                    Style::fg(Color::Rgb(r, g, b)).escape(depth)
+                }
+            })
+            .collect();
+        Palette {
+            classes,
+            bytes,
+            offset: self.offset.escape(depth),
+            separator: self.separator.escape(depth),
+            highlight: self.highlight.escape(depth),
+        }
+    }
+}
+
+/// Escape codes of a theme, ready to write. Empty codes mean no color.
+pub struct Palette {
+    /// Indexed by `ByteClass`.
+    classes: [Vec<u8>; 4],
+    /// Indexed by byte value, following the coloring mode.
+    bytes: Vec<Vec<u8>>,
+    pub offset: Vec<u8>,
+    pub separator: Vec<u8>,
+    pub highlight: Vec<u8>,
+}
+
+impl Palette {
+    /// No escape codes at all, for output without color.
+    pub fn plain() -> Palette {
+        Palette {
+            classes: Default::default(),
+            bytes: vec![Vec::new(); 256],
+            offset: Vec::new(),
+            separator: Vec::new(),
+            highlight: Vec::new(),
+        }
+    }
+
+    #[inline(always)]
+    pub fn class(&self, class: ByteClass) -> &[u8] {
+        &self.classes[class as usize]
+    }
+
+    /// Color of a byte value.
+    #[inline(always)]
+    pub fn byte(&self, byte: u8) -> &[u8] {
+        &self.bytes[byte as usize]
+    }
+}
+
+/// `$XDG_CONFIG_HOME/bred`, or `~/.config/bred`.
+fn config_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        .map(|dir| dir.join("bred"))
+}
+
+/// The theme named in the config file, if the file exists.
+fn read_config(path: &Path) -> io::Result<Option<String>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(io::Error::new(
+                e.kind(),
+                format!("{}: {}", path.display(), e),
+            ))
+        }
+    };
+
+    let mut theme = None;
+    for (line, key, value) in entries(&text, path)? {
+        match key {
+            "theme" => theme = Some(value.to_string()),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "{}:{}: unknown key: {} (expected theme)",
+                        path.display(),
+                        line,
+                        key
+                    ),
+                ))
+            }
+        }
+    }
+    Ok(theme)
+}
+
+/// The `key = value` lines of a config or theme file with their line
+/// numbers, skipping blank lines and `#` comments.
+pub(crate) fn entries<'a>(
+    text: &'a str,
+    path: &Path,
+) -> io::Result<Vec<(usize, &'a str, &'a str)>> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(line, text)| {
+            let (key, value) = text.split_once('=').ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}:{}: expected `key = value`", path.display(), line),
+                )
+            })?;
+            Ok((line, key.trim(), value.trim()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn byte_classes_keep_distinct_colors_at_every_depth() {
+        for (name, theme) in BUILT_IN {
+            for depth in [
+                ColorDepth::Ansi16,
+                ColorDepth::Ansi256,
+                ColorDepth::TrueColor,
+            ] {
+                let palette = theme.palette(depth, Coloring::Class);
+                for (i, a) in palette.classes.iter().enumerate() {
+                    for b in &palette.classes[i + 1..] {
+                        assert_ne!(a, b, "{} theme repeats a class color", name);
+                    }
+                }
+            }
+        }
+    }
+}
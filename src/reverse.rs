@@ -0,0 +1,393 @@
+//! Reverse mode: turn bred's own dumps back into binary.
+
+use crate::hex::{ByteFormat, Endian};
+use crate::output::offset::OffsetFormat;
+use std::io::{self, BufRead, Write};
+
+/// Layout of the dump being read back.
+#[derive(Clone, Copy)]
+pub enum DumpKind {
+    Bytes(ByteFormat),
+    /// xxd layout. The hex column ends two spaces before the character
+    /// column, or after `hex_width` bytes when its short groups are padded.
+    Xxd {
+        hex_width: Option<usize>,
+    },
+    /// Continuous hex digits without offsets.
+    Plain,
+    Binary,
+    Text,
+}
+
+/// Configuration for reading a dump back.
+pub struct ReverseConfig {
+    pub kind: DumpKind,
+    /// Byte order the grouped hex words were printed in.
+    pub endian: Endian,
+    pub offset_format: OffsetFormat,
+    /// Base address that was added to the printed offsets.
+    pub base_address: u64,
+}
+
+/// Parses dump lines and writes the original bytes, zero-filling any gaps
+/// between the offsets of consecutive lines.
+pub struct Reverser {
+    config: ReverseConfig,
+    /// Current line with ANSI color codes removed.
+    line: Vec<u8>,
+    /// Bytes decoded from the current line.
+    bytes: Vec<u8>,
+    /// Bytes decoded from the previous line, repeated to fill a squeezed run.
+    previous: Vec<u8>,
+    /// A `*` line was read: the gap up to the next offset repeats `previous`.
+    repeat: bool,
+    /// Number of bytes written so far.
+    position: u64,
+    /// Bits carried over between binary lines that do not end on a byte boundary.
+    bits: BitAccumulator,
+    line_number: usize,
+}
+
+impl Reverser {
+    pub fn new(config: ReverseConfig) -> Self {
+        Self {
+            config,
+            line: Vec::new(),
+            bytes: Vec::new(),
+            previous: Vec::new(),
+            repeat: false,
+            position: 0,
+            bits: BitAccumulator::default(),
+            line_number: 0,
+        }
+    }
+
+    /// Read the whole dump and write the decoded bytes.
+    pub fn run<R: BufRead, W: Write>(&mut self, mut reader: R, writer: &mut W) -> io::Result<()> {
+        let mut raw = Vec::new();
+        loop {
+            raw.clear();
+            if reader.read_until(b'\n', &mut raw)? == 0 {
+                break;
+            }
+            self.line_number += 1;
+            strip_ansi(&raw, &mut self.line);
+            self.process_line(writer)?;
+        }
+        Ok(())
+    }
+
+    fn process_line<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        while matches!(self.line.last(), Some(b'\n' | b'\r')) {
+            self.line.pop();
+        }
+        if self.line.is_empty() {
+            return Ok(());
+        }
+        if self.line == b"*" {
+            // Only byte dumps squeeze; elsewhere the repeated lines are unknown
+            if let DumpKind::Plain | DumpKind::Binary | DumpKind::Text = self.config.kind {
+                return Err(self.error("`*` lines only appear in hex, octal and decimal dumps"));
+            }
+            self.repeat = true;
+            return Ok(());
+        }
+        if self.line.starts_with(b"-- ") {
+            // The run before a hole or unreadable memory ends at its start;
+            // the skipped bytes are written as zeros
+            let (start, end) = self.parse_marker()?;
+            self.seek_to(start, writer)?;
+            return self.seek_to(end, writer);
+        }
+
+        if let DumpKind::Plain = self.config.kind {
+            self.bytes.clear();
+            parse_plain(&self.line, &mut self.bytes).map_err(|msg| self.error(msg))?;
+            writer.write_all(&self.bytes)?;
+            self.position += self.bytes.len() as u64;
+            return Ok(());
+        }
+
+        let separator = match self.config.kind {
+            DumpKind::Xxd { .. } => b':',
+            _ => b'|',
+        };
+        let Some(sep) = self.line.iter().position(|&b| b == separator) else {
+            return Err(self.error("missing separator after offset"));
+        };
+        let offset = self.parse_offset(&self.line[..sep])?;
+
+        // Data starts after the "| " that follows the offset
+        let rest = &self.line[sep + 1..];
+        let data = rest.strip_prefix(b" ").unwrap_or(rest);
+
+        // Binary lines may start mid-byte; only those on a byte boundary can fill gaps
+        let aligned = self.bits.count == 0;
+
+        self.bytes.clear();
+        let parsed = match self.config.kind {
+            DumpKind::Bytes(format) => {
+                // A second '|' starts the character sidebar
+                let end = data.iter().position(|&b| b == b'|').unwrap_or(data.len());
+                parse_cells(&data[..end], format, self.config.endian, &mut self.bytes)
+            }
+            DumpKind::Xxd { hex_width } => {
+                let end = match hex_width {
+                    Some(width) => width.min(data.len()),
+                    None => data
+                        .windows(2)
+                        .position(|pair| pair == b"  ")
+                        .unwrap_or(data.len()),
+                };
+                parse_cells(
+                    &data[..end],
+                    ByteFormat::Hex,
+                    self.config.endian,
+                    &mut self.bytes,
+                )
+            }
+            DumpKind::Plain => unreachable!("plain dumps have no offsets"),
+            DumpKind::Text => {
+                let text = data.strip_suffix(b" |").unwrap_or(data);
+                parse_text(text, &mut self.bytes)
+            }
+            DumpKind::Binary => {
+                let bits = data.strip_suffix(b" |").unwrap_or(data);
+                parse_bits(bits, &mut self.bits, &mut self.bytes)
+            }
+        };
+        parsed.map_err(|msg| self.error(msg))?;
+
+        if aligned {
+            self.seek_to(offset, writer)?;
+        }
+        writer.write_all(&self.bytes)?;
+        self.position += self.bytes.len() as u64;
+        if !self.bytes.is_empty() {
+            std::mem::swap(&mut self.previous, &mut self.bytes);
+        }
+        Ok(())
+    }
+
+    /// Fill up to `offset`, refusing offsets that go backwards. The gap
+    /// repeats the previous line after a `*`, and is zero-filled otherwise.
+    fn seek_to<W: Write>(&mut self, offset: u64, writer: &mut W) -> io::Result<()> {
+        if offset < self.position {
+            return Err(self.error(&format!(
+                "offset {:#x} overlaps data already written up to {:#x}",
+                offset, self.position
+            )));
+        }
+
+        if std::mem::take(&mut self.repeat) && !self.previous.is_empty() {
+            let mut gap = offset - self.position;
+            while gap > 0 {
+                let n = gap.min(self.previous.len() as u64) as usize;
+                writer.write_all(&self.previous[..n])?;
+                gap -= n as u64;
+            }
+            self.position = offset;
+            return Ok(());
+        }
+
+        const ZEROS: [u8; 4096] = [0u8; 4096];
+        let mut gap = offset - self.position;
+        while gap > 0 {
+            let n = gap.min(ZEROS.len() as u64) as usize;
+            writer.write_all(&ZEROS[..n])?;
+            gap -= n as u64;
+        }
+        self.position = offset;
+        Ok(())
+    }
+
+    fn parse_offset(&self, field: &[u8]) -> io::Result<u64> {
+        let radix = match self.config.offset_format {
+            OffsetFormat::Hex => 16,
+            OffsetFormat::Dec => 10,
+            OffsetFormat::Oct => 8,
+        };
+        let text = std::str::from_utf8(field).unwrap_or("").trim();
+        let offset = u64::from_str_radix(text, radix)
+            .map_err(|_| self.error(&format!("invalid offset '{}'", text)))?;
+        self.unbase(offset)
+    }
+
+    /// Position in the output of a printed offset.
+    fn unbase(&self, offset: u64) -> io::Result<u64> {
+        offset.checked_sub(self.config.base_address).ok_or_else(|| {
+            self.error(&format!(
+                "offset {:#x} is below the base address {:#x}",
+                offset, self.config.base_address
+            ))
+        })
+    }
+
+    /// Parse the `start..end` addresses of a `-- hole 0x..0x.. --` or
+    /// `-- unreadable 0x..0x.. --` line.
+    fn parse_marker(&self) -> io::Result<(u64, u64)> {
+        let text = std::str::from_utf8(&self.line).unwrap_or("");
+        let range = text
+            .strip_prefix("-- hole ")
+            .or_else(|| text.strip_prefix("-- unreadable "))
+            .and_then(|rest| rest.strip_suffix(" --"))
+            .and_then(|range| range.split_once(".."));
+        let parse = |address: &str| {
+            let digits = address.strip_prefix("0x")?;
+            u64::from_str_radix(digits, 16).ok()
+        };
+        match range.and_then(|(start, end)| Some((parse(start)?, parse(end)?))) {
+            Some((start, end)) if start <= end => Ok((self.unbase(start)?, self.unbase(end)?)),
+            _ => Err(self.error("invalid hole or unreadable line")),
+        }
+    }
+
+    fn error(&self, msg: &str) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("line {}: {}", self.line_number, msg),
+        )
+    }
+}
+
+/// Partial byte assembled from binary mode digits.
+#[derive(Default)]
+struct BitAccumulator {
+    value: u8,
+    count: u8,
+}
+
+/// Decode whitespace-separated hex words, octal or decimal bytes.
+fn parse_cells(
+    cells: &[u8],
+    format: ByteFormat,
+    endian: Endian,
+    out: &mut Vec<u8>,
+) -> Result<(), &'static str> {
+    for token in cells.split(|b| b.is_ascii_whitespace()) {
+        if token.is_empty() {
+            continue;
+        }
+
+        match format {
+            ByteFormat::Hex => {
+                if token.len() % 2 != 0 {
+                    return Err("odd number of hex digits");
+                }
+                let start = out.len();
+                for pair in token.chunks_exact(2) {
+                    let hi = hex_value(pair[0]).ok_or("invalid hex digit")?;
+                    let lo = hex_value(pair[1]).ok_or("invalid hex digit")?;
+                    out.push(hi << 4 | lo);
+                }
+                // Little-endian words were printed last byte first
+                if endian == Endian::Little {
+                    out[start..].reverse();
+                }
+            }
+            ByteFormat::Octal | ByteFormat::Decimal => {
+                let radix = if format == ByteFormat::Octal { 8 } else { 10 };
+                let byte = std::str::from_utf8(token)
+                    .ok()
+                    .and_then(|t| u8::from_str_radix(t, radix).ok())
+                    .ok_or("invalid byte value")?;
+                out.push(byte);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Decode continuous hex digits, ignoring whitespace.
+fn parse_plain(line: &[u8], out: &mut Vec<u8>) -> Result<(), &'static str> {
+    let mut digits = line.iter().filter(|b| !b.is_ascii_whitespace());
+    while let Some(&hi) = digits.next() {
+        let lo = *digits.next().ok_or("odd number of hex digits")?;
+        let hi = hex_value(hi).ok_or("invalid hex digit")?;
+        let lo = hex_value(lo).ok_or("invalid hex digit")?;
+        out.push(hi << 4 | lo);
+    }
+    Ok(())
+}
+
+/// Decode text mode output, undoing the escapes from `TEXT_ESCAPE`.
+fn parse_text(text: &[u8], out: &mut Vec<u8>) -> Result<(), &'static str> {
+    let mut i = 0;
+    while i < text.len() {
+        if text[i] != b'\\' {
+            out.push(text[i]);
+            i += 1;
+            continue;
+        }
+
+        let (byte, len) = match text.get(i + 1) {
+            Some(b'n') => (b'\n', 2),
+            Some(b'r') => (b'\r', 2),
+            Some(b't') => (b'\t', 2),
+            Some(b'0') => (0, 2),
+            Some(b'\\') => (b'\\', 2),
+            Some(b'x') => {
+                let digits = text.get(i + 2..i + 4).ok_or("truncated \\x escape")?;
+                let hi = hex_value(digits[0]).ok_or("invalid \\x escape")?;
+                let lo = hex_value(digits[1]).ok_or("invalid \\x escape")?;
+                (hi << 4 | lo, 4)
+            }
+            _ => return Err("invalid escape"),
+        };
+        out.push(byte);
+        i += len;
+    }
+    Ok(())
+}
+
+/// Decode '0'/'1' digits, carrying partial bytes to the next line.
+fn parse_bits(
+    bits: &[u8],
+    acc: &mut BitAccumulator,
+    out: &mut Vec<u8>,
+) -> Result<(), &'static str> {
+    for &bit in bits {
+        let value = match bit {
+            b'0' => 0,
+            b'1' => 1,
+            _ => return Err("invalid bit"),
+        };
+        acc.value = acc.value << 1 | value;
+        acc.count += 1;
+        if acc.count == 8 {
+            out.push(acc.value);
+            acc.value = 0;
+            acc.count = 0;
+        }
+    }
+    Ok(())
+}
+
+/// Copy `src` into `dst` without ANSI escape sequences.
+fn strip_ansi(src: &[u8], dst: &mut Vec<u8>) {
+    dst.clear();
+    let mut i = 0;
+    while i < src.len() {
+        if src[i] == 0x1b && src.get(i + 1) == Some(&b'[') {
+            // Skip parameters up to and including the final byte
+            i += 2;
+            while i < src.len() && !(0x40..=0x7E).contains(&src[i]) {
+                i += 1;
+            }
+            i += 1;
+        } else {
+            dst.push(src[i]);
+            i += 1;
+        }
+    }
+}
+
+#[inline(always)]
+fn hex_value(digit: u8) -> Option<u8> {
+    match digit {
+        b'0'..=b'9' => Some(digit - b'0'),
+        b'a'..=b'f' => Some(digit - b'a' + 10),
+        b'A'..=b'F' => Some(digit - b'A' + 10),
+        _ => None,
+    }
+}
@@ -0,0 +1,550 @@
+//! Search mode: find a pattern and report where it matches, optionally
+//! with the lines around each match.
+
+use crate::hex::TEXT_ESCAPE;
+use crate::input::{MemoryChunk, MemoryReader, READ_BUFFER_SIZE};
+use regex::bytes::{Regex, RegexBuilder};
+use regex_syntax::ParserBuilder;
+use std::collections::VecDeque;
+use std::io::{self, Read};
+
+/// How far back a streamed match may begin when the pattern has no
+/// longest match, such as `a+`. Longer matches across reads are cut short.
+const UNBOUNDED_MATCH_WINDOW: usize = 64 * 1024;
+
+/// A compiled search pattern.
+#[derive(Clone)]
+pub struct Needle {
+    regex: Regex,
+    /// Longest possible match in bytes, if the pattern has one.
+    max_len: Option<usize>,
+}
+
+impl Needle {
+    /// Compile a byte regex. Unicode is off, so `.` and classes match
+    /// single bytes and `\xff` is the byte 0xff.
+    fn compile(pattern: &str) -> Result<Needle, String> {
+        let hir = ParserBuilder::new()
+            .unicode(false)
+            .utf8(false)
+            .dot_matches_new_line(true)
+            .build()
+            .parse(pattern)
+            .map_err(|e| e.to_string())?;
+        if hir.properties().minimum_len() == Some(0) {
+            return Err(format!("pattern matches empty input: {}", pattern));
+        }
+        let regex = RegexBuilder::new(pattern)
+            .unicode(false)
+            .dot_matches_new_line(true)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(Needle {
+            regex,
+            max_len: hir.properties().maximum_len(),
+        })
+    }
+
+    /// Bytes past the end of the data a match starting in it may still need.
+    fn lookahead(&self) -> usize {
+        self.max_len
+            .map_or(UNBOUNDED_MATCH_WINDOW, |len| len.saturating_sub(1))
+    }
+}
+
+/// Parse hex bytes such as `de ad ?? ef`. A `?` digit matches any nibble
+/// and `VV&MM` matches the bytes equal to `VV` in the bits set in `MM`.
+pub fn parse_hex(s: &str) -> Result<Needle, String> {
+    let digits: Vec<char> = s.chars().filter(|c| !c.is_whitespace()).collect();
+    let invalid = || format!("invalid hex pattern: {}", s);
+    let nibble = |c: char, wildcard: bool| match c {
+        '?' if wildcard => Some((0, 0)),
+        _ => c.to_digit(16).map(|d| (d as u8, 0xf)),
+    };
+
+    let mut pattern = String::new();
+    let mut rest = &digits[..];
+    while !rest.is_empty() {
+        let [high, low, tail @ ..] = rest else {
+            return Err(format!("odd number of hex digits: {}", s));
+        };
+        let (high, high_mask) = nibble(*high, true).ok_or_else(invalid)?;
+        let (low, low_mask) = nibble(*low, true).ok_or_else(invalid)?;
+        let (mut value, mut mask) = (high << 4 | low, high_mask << 4 | low_mask);
+        rest = tail;
+
+        if let ['&', high, low, tail @ ..] = rest {
+            if mask != 0xff {
+                return Err(format!("a masked byte cannot use `?`: {}", s));
+            }
+            let (high, _) = nibble(*high, false).ok_or_else(invalid)?;
+            let (low, _) = nibble(*low, false).ok_or_else(invalid)?;
+            mask = high << 4 | low;
+            value &= mask;
+            rest = tail;
+        }
+        push_masked(&mut pattern, value, mask);
+    }
+    if pattern.is_empty() {
+        return Err("empty pattern".to_string());
+    }
+    Needle::compile(&pattern)
+}
+
+/// Parse a string, matched as its UTF-8 bytes.
+pub fn parse_text(s: &str) -> Result<Needle, String> {
+    let mut pattern = String::new();
+    for &byte in s.as_bytes() {
+        push_masked(&mut pattern, byte, 0xff);
+    }
+    Needle::compile(&pattern)
+}
+
+/// Parse a string, matched in UTF-16 of either byte order.
+pub fn parse_utf16(s: &str) -> Result<Needle, String> {
+    let (mut little, mut big) = (String::new(), String::new());
+    for unit in s.encode_utf16() {
+        for byte in unit.to_le_bytes() {
+            push_masked(&mut little, byte, 0xff);
+        }
+        for byte in unit.to_be_bytes() {
+            push_masked(&mut big, byte, 0xff);
+        }
+    }
+    Needle::compile(&format!("{}|{}", little, big))
+}
+
+/// Parse a regular expression over bytes.
+pub fn parse_regex(s: &str) -> Result<Needle, String> {
+    Needle::compile(s)
+}
+
+/// Append a regex matching the bytes equal to `value` in the bits of `mask`.
+fn push_masked(pattern: &mut String, value: u8, mask: u8) {
+    if mask == 0xff {
+        pattern.push_str(&format!("\\x{:02x}", value));
+        return;
+    }
+
+    // A class of every matching byte, as ranges
+    pattern.push('[');
+    let mut byte = 0u16;
+    while byte <= 0xff {
+        if byte as u8 & mask != value {
+            byte += 1;
+            continue;
+        }
+        let first = byte;
+        while byte < 0xff && (byte + 1) as u8 & mask == value {
+            byte += 1;
+        }
+        if byte == first {
+            pattern.push_str(&format!("\\x{:02x}", first));
+        } else {
+            pattern.push_str(&format!("\\x{:02x}-\\x{:02x}", first, byte));
+        }
+        byte += 1;
+    }
+    pattern.push(']');
+}
+
+/// Receives what a search finds. Offsets are printed offsets.
+pub trait Sink {
+    /// A match of `bytes` at `offset`.
+    fn found(&mut self, offset: u64, bytes: &[u8]) -> io::Result<()>;
+
+    /// The lines around one or more nearby matches: `data` is printed from
+    /// `offset` and the matches cover `spans`, as `(start, end)` offsets.
+    fn context(&mut self, data: &[u8], offset: u64, spans: &[(u64, u64)]) -> io::Result<()>;
+}
+
+/// Lines shown around each match.
+#[derive(Clone, Copy)]
+pub struct Context {
+    pub lines: u64,
+    pub breaks: LineBreaks,
+}
+
+/// Where the dump breaks its lines.
+#[derive(Clone, Copy)]
+pub enum LineBreaks {
+    /// Every line shows this many input bytes.
+    Every(u64),
+    /// Text mode: a line ends when the next escape would not fit in
+    /// `width` characters, so the breaks depend on every earlier byte.
+    Text { width: usize },
+}
+
+/// Matches sharing one context dump.
+struct Group {
+    start: u64,
+    /// End of the last match, which the context runs on from.
+    stop: u64,
+    spans: Vec<(u64, u64)>,
+}
+
+/// Line starts of a text dump, found by following its wrapping through
+/// every byte searched.
+struct TextLines {
+    width: usize,
+    /// Offset the wrapping has been followed to.
+    scanned: u64,
+    /// Characters on the line being filled at `scanned`.
+    char_pos: usize,
+    /// Line starts in order, from the oldest one context may still reach.
+    starts: VecDeque<u64>,
+}
+
+impl TextLines {
+    fn new(width: usize, offset: u64) -> Self {
+        Self {
+            width,
+            scanned: offset,
+            char_pos: 0,
+            starts: VecDeque::from([offset]),
+        }
+    }
+
+    /// Follow the wrapping through the unscanned part of `data`, printed
+    /// from `offset`.
+    fn scan(&mut self, data: &[u8], offset: u64) {
+        let mut at = self.scanned;
+        for &byte in &data[(self.scanned - offset) as usize..] {
+            let len = TEXT_ESCAPE[byte as usize].1 as usize;
+            if self.char_pos > 0 && self.char_pos + len > self.width {
+                self.starts.push_back(at);
+                self.char_pos = 0;
+            }
+            self.char_pos += len;
+            at += 1;
+            if self.char_pos >= self.width {
+                self.starts.push_back(at);
+                self.char_pos = 0;
+            }
+        }
+        self.scanned = at;
+    }
+
+    /// Index in `starts` of the line holding the byte at `offset`.
+    fn line_of(&self, offset: u64) -> usize {
+        self.starts
+            .partition_point(|&start| start <= offset)
+            .saturating_sub(1)
+    }
+
+    /// Forget the lines before the one holding `offset`.
+    fn forget_before(&mut self, offset: u64) {
+        let line = self.line_of(offset);
+        self.starts.drain(..line);
+    }
+}
+
+/// Finds the matches of a needle in data that arrives a piece at a time,
+/// each piece starting where the kept part of the previous one ends.
+pub struct Searcher<'a> {
+    needle: &'a Needle,
+    /// Most matches to report, for `--first`.
+    limit: Option<u64>,
+    context: Option<Context>,
+    /// Printed offset of the first input byte; context lines start from it.
+    origin: u64,
+    /// Offset context may not reach back past: the origin, or the end of
+    /// the last stretch of unreadable memory.
+    floor: u64,
+    /// Text mode line starts, when showing context.
+    text: Option<TextLines>,
+    found: u64,
+    /// Offset where the next match may start.
+    next: u64,
+    group: Option<Group>,
+}
+
+impl<'a> Searcher<'a> {
+    pub fn new(
+        needle: &'a Needle,
+        limit: Option<u64>,
+        context: Option<Context>,
+        origin: u64,
+    ) -> Self {
+        let text = match context.map(|context| context.breaks) {
+            Some(LineBreaks::Text { width }) => Some(TextLines::new(width, origin)),
+            _ => None,
+        };
+        Self {
+            needle,
+            limit,
+            context,
+            origin,
+            floor: origin,
+            text,
+            found: 0,
+            next: origin,
+            group: None,
+        }
+    }
+
+    /// Number of matches found so far.
+    pub fn found(&self) -> u64 {
+        self.found
+    }
+
+    /// Whether the limit is reached and every match has been reported.
+    pub fn done(&self) -> bool {
+        self.limit.is_some_and(|limit| self.found >= limit) && self.group.is_none()
+    }
+
+    /// Carry on at `offset` after bytes that could not be read. No match or
+    /// context spans them, so everything before must have been searched
+    /// with `last` set.
+    pub fn resume_at(&mut self, offset: u64) {
+        self.next = offset;
+        self.floor = offset;
+        // The dump starts a fresh line after the gap
+        if let Some(text) = &mut self.text {
+            *text = TextLines::new(text.width, offset);
+        }
+    }
+
+    /// Search `data`, printed from `offset`, which must start at or before
+    /// the offset the previous call returned. Matches that may run past
+    /// its end are left for the next call unless `last`. Returns the offset
+    /// of the first byte still needed.
+    pub fn search<S: Sink>(
+        &mut self,
+        data: &[u8],
+        offset: u64,
+        last: bool,
+        sink: &mut S,
+    ) -> io::Result<u64> {
+        let end = offset.checked_add(data.len() as u64).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("search offsets pass {:#x}", u64::MAX),
+            )
+        })?;
+        let settled = if last {
+            data.len()
+        } else {
+            data.len().saturating_sub(self.needle.lookahead())
+        };
+        if let Some(text) = &mut self.text {
+            text.scan(data, offset);
+        }
+
+        let mut at = (self.next - offset) as usize;
+        while self.limit.is_none_or(|limit| self.found < limit) {
+            let Some(m) = self.needle.regex.find_at(data, at) else {
+                at = at.max(settled);
+                break;
+            };
+            if m.start() >= settled {
+                at = settled.max(at);
+                break;
+            }
+            let (start, stop) = (offset + m.start() as u64, offset + m.end() as u64);
+            self.found += 1;
+            sink.found(start, m.as_bytes())?;
+            if self.context.is_some() {
+                self.add_to_group(start, stop, data, offset, sink)?;
+            }
+            at = m.end();
+        }
+        self.next = offset + at as u64;
+
+        // A group is dumped once its last line has arrived
+        if let Some(group) = &self.group {
+            let group_end = match self.context_end(group.stop) {
+                Some(group_end) if group_end <= end => Some(group_end),
+                _ if last => Some(end),
+                _ => None,
+            };
+            if let Some(group_end) = group_end {
+                self.flush_group(group_end, data, offset, sink)?;
+            }
+        }
+
+        let needed = match &self.group {
+            Some(group) => group.start,
+            None => self.context_start(self.next),
+        }
+        .min(self.next);
+        if let Some(text) = &mut self.text {
+            text.forget_before(needed);
+        }
+        Ok(needed)
+    }
+
+    /// Offset where the context of a match starting at `start` begins.
+    fn context_start(&self, start: u64) -> u64 {
+        let Some(context) = self.context else {
+            return start;
+        };
+        match context.breaks {
+            LineBreaks::Every(line_bytes) => {
+                let line = self.origin + (start - self.origin) / line_bytes * line_bytes;
+                line.saturating_sub(context.lines * line_bytes)
+                    .max(self.floor)
+            }
+            LineBreaks::Text { .. } => {
+                let text = self.text.as_ref().expect("text lines are followed");
+                let line = text.line_of(start).saturating_sub(context.lines as usize);
+                text.starts[line]
+            }
+        }
+    }
+
+    /// Offset where the context of a match ending at `stop` ends, or `None`
+    /// while its last line has not been read yet.
+    fn context_end(&self, stop: u64) -> Option<u64> {
+        let context = self.context?;
+        match context.breaks {
+            LineBreaks::Every(line_bytes) => {
+                let lines = (stop - self.origin).div_ceil(line_bytes) + context.lines;
+                Some(self.origin.saturating_add(lines.saturating_mul(line_bytes)))
+            }
+            LineBreaks::Text { .. } => {
+                let text = self.text.as_ref().expect("text lines are followed");
+                let line = (text.line_of(stop - 1) + 1).saturating_add(context.lines as usize);
+                text.starts.get(line).copied()
+            }
+        }
+    }
+
+    /// Add a match to the current group, first dumping the group if the
+    /// match's context does not touch it.
+    fn add_to_group<S: Sink>(
+        &mut self,
+        start: u64,
+        stop: u64,
+        data: &[u8],
+        offset: u64,
+        sink: &mut S,
+    ) -> io::Result<()> {
+        let first = self.context_start(start);
+        if let Some(group) = &self.group {
+            // A group whose last line is not read yet reaches past this match
+            match self.context_end(group.stop) {
+                Some(group_end) if first > group_end => {
+                    self.flush_group(group_end, data, offset, sink)?
+                }
+                _ => {}
+            }
+        }
+        match &mut self.group {
+            Some(group) => {
+                group.stop = group.stop.max(stop);
+                group.spans.push((start, stop));
+            }
+            None => {
+                self.group = Some(Group {
+                    start: first,
+                    stop,
+                    spans: vec![(start, stop)],
+                })
+            }
+        }
+        Ok(())
+    }
+
+    /// Dump the current group, up to `group_end`.
+    fn flush_group<S: Sink>(
+        &mut self,
+        group_end: u64,
+        data: &[u8],
+        offset: u64,
+        sink: &mut S,
+    ) -> io::Result<()> {
+        let Some(group) = self.group.take() else {
+            return Ok(());
+        };
+        let start = (group.start - offset) as usize;
+        let end = ((group_end - offset) as usize).min(data.len());
+        sink.context(&data[start..end], group.start, &group.spans)
+    }
+}
+
+/// Search all of `data`, printed from `offset`.
+pub fn search_slice<S: Sink>(
+    searcher: &mut Searcher,
+    data: &[u8],
+    offset: u64,
+    sink: &mut S,
+) -> io::Result<()> {
+    searcher.search(data, offset, true, sink).map(|_| ())
+}
+
+/// Search everything `reader` produces, printed from `offset`.
+pub fn search_reader<R: Read, S: Sink>(
+    searcher: &mut Searcher,
+    mut reader: R,
+    offset: u64,
+    sink: &mut S,
+) -> io::Result<()> {
+    let next = |buf: &mut [u8]| match reader.read(buf)? {
+        0 => Ok(None),
+        n => Ok(Some(MemoryChunk::Data(n))),
+    };
+    search_chunks(searcher, next, offset, sink)
+}
+
+/// Search process memory, printed from `offset`. Matches and their
+/// context stop at unreadable memory.
+pub fn search_memory<S: Sink>(
+    searcher: &mut Searcher,
+    mut memory: MemoryReader,
+    offset: u64,
+    sink: &mut S,
+) -> io::Result<()> {
+    search_chunks(searcher, |buf| memory.next(buf), offset, sink)
+}
+
+/// Search the chunks `next` reads, keeping the bytes a match or its
+/// context may still need between reads.
+fn search_chunks<F, S>(
+    searcher: &mut Searcher,
+    mut next: F,
+    offset: u64,
+    sink: &mut S,
+) -> io::Result<()>
+where
+    F: FnMut(&mut [u8]) -> io::Result<Option<MemoryChunk>>,
+    S: Sink,
+{
+    let mut buffer = Vec::new();
+    let mut buffer_offset = offset;
+    loop {
+        let kept = buffer.len();
+        buffer.resize(kept + READ_BUFFER_SIZE, 0);
+        let chunk = next(&mut buffer[kept..])?;
+        let n = match chunk {
+            Some(MemoryChunk::Data(n)) => n,
+            _ => 0,
+        };
+        buffer.truncate(kept + n);
+
+        match chunk {
+            None => {
+                searcher.search(&buffer, buffer_offset, true, sink)?;
+                return Ok(());
+            }
+            Some(MemoryChunk::Data(_)) => {
+                let needed = searcher.search(&buffer, buffer_offset, false, sink)?;
+                if searcher.done() {
+                    return Ok(());
+                }
+                buffer.drain(..(needed - buffer_offset) as usize);
+                buffer_offset = needed;
+            }
+            Some(MemoryChunk::Unreadable(len)) => {
+                // What was read so far is searched as if it ended here
+                searcher.search(&buffer, buffer_offset, true, sink)?;
+                if searcher.done() {
+                    return Ok(());
+                }
+                buffer_offset = buffer_offset.wrapping_add(buffer.len() as u64 + len);
+                buffer.clear();
+                searcher.resume_at(buffer_offset);
+            }
+        }
+    }
+}
@@ -0,0 +1,626 @@
+//! Command-line tests, run against the built binary.
+
+use std::fs;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// Run bred with `args`, feeding `input` on stdin.
+fn bred(args: &[&str], input: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bred"))
+        .args(args)
+        .env("TERM", "xterm-256color")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("bred runs");
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = input.to_vec();
+    let writer = thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output().expect("bred finishes");
+    // bred may stop reading early, closing the pipe
+    let _ = writer.join();
+    output
+}
+
+/// Standard output of a successful run.
+fn stdout(args: &[&str], input: &[u8]) -> String {
+    let output = bred(args, input);
+    assert!(
+        output.status.success(),
+        "bred {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("output is UTF-8")
+}
+
+/// Path of a file in the tests directory.
+fn test_data(name: &str) -> String {
+    format!("{}/tests/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+/// Dump `path` with `args`, reverse the dump with the same arguments and
+/// check the original bytes come back.
+fn assert_round_trip(args: &[&str], path: &str) {
+    let dump = stdout(&[args, &[path]].concat(), b"");
+    let output = bred(&[&["-r"], args].concat(), dump.as_bytes());
+    assert!(
+        output.status.success(),
+        "bred -r {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        output.stdout == fs::read(path).unwrap(),
+        "bred {:?} does not reverse",
+        args
+    );
+}
+
+/// Write `data` to a file unique to this test and return its path.
+fn temp_file(name: &str, data: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("bred-test-{}-{}", std::process::id(), name));
+    fs::write(&path, data).expect("temp file is written");
+    path
+}
+
+#[test]
+fn offsets_wrap_at_the_top_of_the_address_space() {
+    let data = [0x41u8; 40];
+    let base = ["--base-address", "0xfffffffffffffff8"];
+
+    let hex = stdout(&[&["-x", "-v"][..], &base].concat(), &data);
+    let offsets: Vec<&str> = hex.lines().map(|line| &line[..16]).collect();
+    assert_eq!(
+        offsets,
+        ["fffffffffffffff8", "0000000000000008", "0000000000000018"]
+    );
+
+    let binary = stdout(&[&["-b"][..], &base].concat(), &data);
+    assert!(binary
+        .lines()
+        .nth(1)
+        .unwrap()
+        .starts_with("0000000000000000|"));
+
+    let highlighted = [
+        &["-x", "-v", "--color=always"][..],
+        &base,
+        &["--highlight-range", "0..4=red"],
+    ]
+    .concat();
+    let highlighted = stdout(&highlighted, &data);
+    assert!(highlighted.contains("0000000000000008"));
+    assert!(highlighted.lines().next().unwrap().contains("\x1b[31m41"));
+}
+
+#[test]
+fn base_address_past_the_end_of_a_file_is_rejected() {
+    let path = temp_file("base-address", &[0u8; 40]);
+    let path = path.to_str().unwrap();
+
+    // The offset just past the data is the largest there is
+    let fits = stdout(&["-x", "--base-address", "0xffffffffffffffd7", path], b"");
+    assert!(fits.starts_with("ffffffffffffffd7|"));
+
+    let output = bred(&["-x", "--base-address", "0xffffffffffffffd8", path], b"");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("puts offsets past"));
+    fs::remove_file(path).unwrap();
+}
+
+const XXD_INPUT: &[u8] = b"Page VICONSTITUTION OF THE UNI";
+
+/// Output of `xxd -c 7`.
+const XXD_C7: &str = "\
+00000000: 5061 6765 2056 49  Page VI
+00000007: 434f 4e53 5449 54  CONSTIT
+0000000e: 5554 494f 4e20 4f  UTION O
+00000015: 4620 5448 4520 55  F THE U
+0000001c: 4e49               NI
+";
+
+/// Output of `xxd -c 5 -g 4`.
+const XXD_C5_G4: &str = "\
+00000000: 50616765 20  Page 
+00000005: 5649434f 4e  VICON
+0000000a: 53544954 55  STITU
+0000000f: 54494f4e 20  TION 
+00000014: 4f462054 48  OF TH
+00000019: 4520554e 49  E UNI
+";
+
+#[test]
+fn xxd_layout_matches_xxd_with_odd_columns() {
+    assert_eq!(stdout(&["--xxd", "-l", "7"], XXD_INPUT), XXD_C7);
+    assert_eq!(
+        stdout(&["--xxd", "-l", "5", "-g", "4"], XXD_INPUT),
+        XXD_C5_G4
+    );
+}
+
+#[test]
+fn xxd_output_with_odd_columns_reverses() {
+    for dump in [XXD_C7, XXD_C5_G4] {
+        let output = bred(&["-r", "--xxd"], dump.as_bytes());
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        assert_eq!(output.stdout, XXD_INPUT);
+    }
+}
+
+#[test]
+fn holes_are_skipped_in_every_line_mode() {
+    let path = temp_file("holes", b"abc");
+    let file = fs::OpenOptions::new().write(true).open(&path).unwrap();
+    file.set_len(256 << 20).unwrap();
+    let path = path.to_str().unwrap();
+
+    for mode in [&["-x", "-v"][..], &[], &["-b"], &["-t", "u32"]] {
+        let dump = stdout(&[mode, &[path]].concat(), b"");
+        assert!(dump.len() < 64 * 1024, "{:?} printed the hole", mode);
+        assert!(dump.contains("-- hole 0x"), "{:?} has no hole line", mode);
+    }
+
+    // The page holding the data is written as records, the hole is not
+    let records = stdout(&["--ihex", path], b"");
+    assert!(records.len() < 64 * 1024);
+    assert!(records.starts_with(":10000000616263"));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn followed_partial_lines_are_printed_while_idle() {
+    let path = temp_file("follow", &[b'A'; 35]);
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bred"))
+        .args(["-x", "-f", path.to_str().unwrap()])
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("bred runs");
+
+    // The output is read until the partial line has shown up
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buf = [0u8; 4096];
+        while let Ok(n @ 1..) = stdout.read(&mut buf) {
+            if sender.send(buf[..n].to_vec()).is_err() {
+                break;
+            }
+        }
+    });
+    let mut dump = Vec::new();
+    while !dump.ends_with(b"41 41 41 \n") {
+        match receiver.recv_timeout(Duration::from_secs(10)) {
+            Ok(bytes) => dump.extend_from_slice(&bytes),
+            Err(_) => break,
+        }
+    }
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    // Both full lines are the same but neither is squeezed
+    let dump = String::from_utf8(dump).unwrap();
+    let offsets: Vec<&str> = dump.lines().map(|line| &line[..7]).collect();
+    assert_eq!(offsets, ["0000000", "0000010", "0000020"]);
+    fs::remove_file(path).unwrap();
+}
+
+/// `printf 'hello\n' | gzip -n`.
+const HELLO_GZ: &[u8] = b"\x1f\x8b\x08\x00\x00\x00\x00\x00\x00\x03\xcb\x48\xcd\xc9\xc9\xe7\x02\x00\x20\x30\x3a\x36\x06\x00\x00\x00";
+
+#[test]
+fn only_dumps_decompress_unless_asked() {
+    assert!(stdout(&["-x"], HELLO_GZ).starts_with("0000000| 68 65 6c 6c 6f 0a"));
+    let notice = "gzip-compressed, dumping the decompressed bytes";
+    assert!(String::from_utf8_lossy(&bred(&["-x"], HELLO_GZ).stderr).contains(notice));
+    let forced = bred(&["-x", "--decompress", "gzip"], HELLO_GZ);
+    assert!(forced.stderr.is_empty());
+    let path = temp_file("hello.gz", HELLO_GZ);
+    let path = path.to_str().unwrap();
+    let stderr = String::from_utf8(bred(&["-x", path], b"").stderr).unwrap();
+    assert!(
+        stderr.starts_with(&format!("{}: {}", path, notice)),
+        "{}",
+        stderr
+    );
+    fs::remove_file(path).unwrap();
+    assert_eq!(
+        stdout(&["--find-text", "hello"], HELLO_GZ),
+        "0000000| 68 65 6c 6c 6f\n"
+    );
+
+    assert_eq!(
+        stdout(&["--encode", "base64"], HELLO_GZ),
+        "H4sIAAAAAAAAA8tIzcnJ5wIAIDA6NgYAAAA=\n"
+    );
+    assert!(stdout(&["--include", "c"], HELLO_GZ).contains("0x1f, 0x8b"));
+    assert!(stdout(&["--ihex"], HELLO_GZ).starts_with(":100000001F8B"));
+    assert!(stdout(&["--srec"], HELLO_GZ).contains("\nS315000000001F8B"));
+
+    assert_eq!(
+        stdout(&["--encode", "base64", "--decompress", "auto"], HELLO_GZ),
+        "aGVsbG8K\n"
+    );
+}
+
+/// Intel HEX with records at 0x0 and 0x40000008, a GiB apart.
+const FAR_RECORDS: &str = "\
+:1000000068656C6C6F20776F726C642C2068692156
+:03001000616263C7
+:020000044000BA
+:080008006661722061776179E5
+:00000001FF
+";
+
+#[test]
+fn gaps_between_records_are_not_filled() {
+    let path = temp_file("far.hex", FAR_RECORDS.as_bytes());
+    let path = path.to_str().unwrap();
+
+    let output = bred(&["--import", "ihex", "-x", path], b"");
+    assert!(output.status.success());
+    let dump = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(dump.lines().count(), 5);
+    assert!(dump.contains("-- hole 0x20..0x40000000 --"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Gap 0x13..0x40000008"));
+
+    // Converting the records keeps the gap out of the output
+    let records = stdout(&["--import", "ihex", "--ihex", path], b"");
+    assert_eq!(records, FAR_RECORDS);
+
+    let found = stdout(&["--import", "ihex", "--find-text", "far", path], b"");
+    assert_eq!(found, "40000008| 66 61 72\n");
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn records_with_bad_checksums_are_rejected() {
+    // The last record of FAR_RECORDS with its checksum off by one
+    let bad = FAR_RECORDS.replace(":080008006661722061776179E5", ":080008006661722061776179E6");
+    let output = bred(&["--import", "ihex", "-x"], bad.as_bytes());
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("line 4: checksum mismatch"));
+
+    let output = bred(
+        &["--import", "srec", "-x"],
+        b"S0030000FC\nS1060000616263FF\n",
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("line 2: checksum mismatch"));
+
+    let good = stdout(
+        &["--import", "srec", "-x"],
+        b"S0030000FC\nS1060000616263D3\n",
+    );
+    assert!(good.starts_with("0000000| 61 62 63"));
+}
+
+#[test]
+fn compressed_headers_label_the_size_on_disk() {
+    let path = temp_file("hello.gz", HELLO_GZ);
+    let path = path.to_str().unwrap();
+    let dump = stdout(&["-x", path, path], b"");
+    let header = format!("==> {} (gzip-compressed, 26 bytes on disk) <==", path);
+    assert!(dump.starts_with(&header), "{}", dump);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn concat_reads_more_files_than_can_be_mapped() {
+    // More than the 64 mappings protected against files shrinking
+    let paths: Vec<PathBuf> = (0..80u8)
+        .map(|i| temp_file(&format!("concat-{}", i), &[i; 64 * 1024]))
+        .collect();
+    let mut args = vec!["--concat", "--find", "4e 4e 4f"];
+    args.extend(paths.iter().map(|path| path.to_str().unwrap()));
+
+    // The only place 4e 4e is followed by 4f is where file 79 starts
+    let found = stdout(&args, b"");
+    assert_eq!(found, "04efffe| 4e 4e 4f\n");
+    for path in paths {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn offsets_below_the_base_address_are_rejected() {
+    let dump = stdout(&["-x"], b"hello");
+    let output = bred(&["-r", "-x", "--base-address", "0x1000"], dump.as_bytes());
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("line 1: offset 0x0 is below the base address 0x1000"));
+
+    let dump = stdout(&["-x", "--base-address", "0x1000"], b"hello");
+    assert_eq!(
+        bred(&["-r", "-x", "--base-address", "0x1000"], dump.as_bytes()).stdout,
+        b"hello"
+    );
+}
+
+#[test]
+fn text_dumps_that_cannot_be_reversed_are_rejected() {
+    let output = bred(&["-r"], b"0000000| ab |\n*\n0000010| cd |\n");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("line 2: `*` lines"));
+
+    let dump = stdout(&["-s"], b"a b_c");
+    let output = bred(&["-r", "-s"], dump.as_bytes());
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("does not support --space"));
+}
+
+#[test]
+fn source_bin_reverses_in_every_layout() {
+    let path = test_data("source.bin");
+    for args in [
+        &["-x"][..],
+        &["-x", "-C"],
+        &["-x", "-v"],
+        &["-x", "-g", "4", "-e", "little", "-C"],
+        &["-x", "-g", "8", "-l", "12"],
+        &["-o", "-C"],
+        &["-d"],
+        &["-x", "--offset-format", "dec"],
+        &["-x", "--base-address", "0x1000"],
+        &["-x", "--color=always"],
+        &["-b"],
+        &["-b", "-l", "13"],
+        &["-b", "--color=always"],
+        &[],
+        &["-l", "7"],
+        &["--color=always"],
+        &["-p"],
+    ] {
+        assert_round_trip(args, &path);
+    }
+}
+
+#[test]
+fn source_bin_reverses_in_xxd_layouts() {
+    let path = test_data("source.bin");
+    for args in [
+        &["--xxd"][..],
+        &["--xxd", "-l", "7"],
+        &["--xxd", "-l", "5", "-g", "4"],
+        &["--xxd", "-g", "4", "-e", "little"],
+        &["--xxd", "-l", "13", "-g", "4", "-e", "little"],
+    ] {
+        assert_round_trip(args, &path);
+    }
+}
+
+/// Bytes that do not repeat for a long while, to cross read chunks.
+fn noise(len: usize) -> Vec<u8> {
+    let mut state = 0x2545f4914f6cdd1du64;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
+#[test]
+fn encodings_round_trip() {
+    let path = test_data("source.bin");
+    let noise = noise(600 * 1024 + 7);
+    for encoding in ["base64", "base32", "ascii85"] {
+        assert_round_trip(&["--encode", encoding], &path);
+        assert_round_trip(&["--encode", encoding, "-l", "17"], &path);
+
+        // Stdin arrives in chunks that do not end on an encoding group
+        let encoded = stdout(&["--encode", encoding], &noise);
+        let decoded = bred(&["-r", "--encode", encoding], encoded.as_bytes());
+        assert!(decoded.stdout == noise, "{} does not round-trip", encoding);
+
+        // --decode turns the text back into bytes for any dump mode
+        assert_eq!(
+            stdout(&["--decode", encoding, "-x", "-v"], encoded.as_bytes()),
+            stdout(&["-x", "-v"], &noise),
+        );
+    }
+    assert_eq!(stdout(&["--encode", "base64"], b"hello"), "aGVsbG8=\n");
+    assert_eq!(stdout(&["--encode", "base32"], b"hello"), "NBSWY3DP\n");
+    assert_eq!(stdout(&["--encode", "ascii85"], b"hello"), "BOu!rDZ\n");
+}
+
+#[test]
+fn repeated_lines_are_squeezed() {
+    let mut data = vec![b'a'; 16];
+    data.extend_from_slice(&[0; 64]);
+    data.extend_from_slice(b"bbbbb");
+    assert_eq!(
+        stdout(&["-x"], &data),
+        "\
+0000000| 61 61 61 61 61 61 61 61 61 61 61 61 61 61 61 61 |
+0000010| 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 |
+*
+0000050| 62 62 62 62 62 
+"
+    );
+    assert_eq!(stdout(&["-x", "-v"], &data).lines().count(), 6);
+
+    // A run longer than a read chunk is still one `*` line
+    let mut data = vec![0xffu8; 1 << 20];
+    data.extend_from_slice(b"end");
+    let dump = stdout(&["-x"], &data);
+    assert_eq!(dump.matches("*\n").count(), 1);
+    assert!(dump.contains("*\n0100000| 65 6e 64"), "{}", dump);
+    let reversed = bred(&["-r", "-x"], dump.as_bytes());
+    assert!(reversed.stdout == data);
+}
+
+#[test]
+fn search_counts_and_shows_context() {
+    let data: Vec<u8> = (0x40..0xa0).collect();
+    assert_eq!(
+        stdout(&["-x", "--find", "60 ?1 60&f0", "--context", "1"], &data),
+        "\
+0000010| 50 51 52 53 54 55 56 57 58 59 5a 5b 5c 5d 5e 5f |
+0000020| 60 61 62 63 64 65 66 67 68 69 6a 6b 6c 6d 6e 6f |
+0000030| 70 71 72 73 74 75 76 77 78 79 7a 7b 7c 7d 7e 7f |
+0000040| 
+"
+    );
+    let text = b"xxabcxxxxabcyyABC";
+    assert_eq!(
+        stdout(&["--find-text", "abc", "--count-matches"], text),
+        "2\n"
+    );
+    assert_eq!(
+        stdout(&["--find-regex", "(?i)abc", "--count-matches"], text),
+        "3\n"
+    );
+    assert_eq!(
+        stdout(&["--find-text", "abc", "--first"], text),
+        "0000002| 61 62 63\n"
+    );
+    assert!(!bred(&["--find-text", "abcd"], text).status.success());
+}
+
+#[test]
+fn search_finds_matches_across_read_chunks() {
+    // Streamed input is read 256 KiB at a time
+    let mut data = noise(1 << 20);
+    for at in [(256 << 10) - 3, (512 << 10) - 1, 700_000] {
+        data[at..at + 7].copy_from_slice(b"NEEDLE!");
+    }
+    let expected = "\
+003fffd| 4e 45 45 44 4c 45 21
+007ffff| 4e 45 45 44 4c 45 21
+00aae60| 4e 45 45 44 4c 45 21
+";
+    assert_eq!(stdout(&["--find-text", "NEEDLE!"], &data), expected);
+
+    // The same through a mapped file
+    let path = temp_file("search", &data);
+    let path = path.to_str().unwrap();
+    assert_eq!(stdout(&["--find-text", "NEEDLE!", path], b""), expected);
+    assert_eq!(
+        stdout(&["--find-regex", "NEE+DLE!", "--count-matches", path], b""),
+        "3\n"
+    );
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn tail_lines_keep_whole_values() {
+    let data = b"0123456789abcdefghijklmnopqrstuvwxyzABCD";
+    // Four bytes per line still holds one u64 per line
+    assert_eq!(
+        stdout(&["--tail-lines", "2", "-t", "u64", "-l", "4"], data),
+        "0000018|  8535856707940741231 |\n0000020|  4918848067013802103 |\n0000028| \n"
+    );
+    assert_eq!(
+        stdout(&["--tail-lines", "1", "-t", "u32", "-l", "10"], data),
+        "0000020| 2054781047 1145258561 |\n0000028| \n"
+    );
+
+    // Text lines depend on the escapes of everything before them
+    let output = bred(&["--tail-lines", "1"], data);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("text mode"));
+}
+
+#[test]
+fn text_context_follows_the_line_wrapping() {
+    // Escapes make text lines uneven, and streamed input is read 256 KiB at a time
+    let mut data = noise(1 << 20);
+    for at in [(256 << 10) - 3, 700_000] {
+        data[at..at + 7].copy_from_slice(b"NEEDLE!");
+    }
+    let path = temp_file("text-context", &data);
+    let path = path.to_str().unwrap();
+    let dump = stdout(&[path], b"");
+    let dump: Vec<&str> = dump.lines().collect();
+
+    for input in [&[path][..], &[]] {
+        let args = [&["--find-text", "NEEDLE!", "--context", "2"][..], input].concat();
+        let context = stdout(&args, &data);
+        let groups: Vec<Vec<&str>> = context
+            .split("\n--\n")
+            .map(|group| group.lines().filter(|line| line.ends_with(" |")).collect())
+            .collect();
+        assert_eq!(groups.len(), 2);
+        for lines in groups {
+            // Two lines either side of the one or two the match is on
+            assert!(lines.len() == 5 || lines.len() == 6, "{:?}", lines);
+            assert!(
+                dump.windows(lines.len()).any(|window| window == lines),
+                "{:?} are not lines of the full dump",
+                lines
+            );
+        }
+    }
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn widest_floats_stay_in_their_columns() {
+    let values = [-f32::MAX, -123456790000000.0, 1.0, -f32::MIN_POSITIVE];
+    let data: Vec<u8> = values
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect();
+    assert_eq!(
+        stdout(&["-t", "f32"], &data),
+        "0000000|    -3.4028235e38 -123456790000000                1   -1.1754944e-38 |\n0000010| \n"
+    );
+}
+
+#[test]
+fn broken_color_files_only_matter_with_colors() {
+    let broken = ["--theme", "/nonexistent/bred.theme"];
+    let rules = ["--highlight-file", "/nonexistent/bred.rules"];
+    for args in [&broken, &rules] {
+        assert_eq!(
+            stdout(&[&args[..], &["-x"]].concat(), b"abc"),
+            "0000000| 61 62 63 \n"
+        );
+        assert_eq!(
+            stdout(&[&args[..], &["-x", "--color=never"]].concat(), b"abc"),
+            "0000000| 61 62 63 \n"
+        );
+        assert!(
+            !bred(&[&args[..], &["-x", "--color=always"]].concat(), b"abc")
+                .status
+                .success()
+        );
+    }
+}
+
+#[test]
+fn later_patterns_win_across_read_chunks() {
+    // `B` straddles every page, where reads from a pipe tend to stop
+    let mut data = vec![0u8; 1 << 20];
+    for page in (4096..data.len()).step_by(4096) {
+        data[page - 2..page + 1].copy_from_slice(b"ABC");
+    }
+    let args = [
+        "-x",
+        "--color=always",
+        "--threads",
+        "1",
+        "--highlight-pattern",
+        "\"AB\"=red",
+        "--highlight-pattern",
+        "\"BC\"=blue",
+    ];
+    let path = temp_file("patterns", &data);
+    let path = path.to_str().unwrap();
+    let whole = stdout(&[&args[..], &[path]].concat(), b"");
+    assert!(whole.contains("42"));
+    assert_eq!(stdout(&args, &data), whole);
+    fs::remove_file(path).unwrap();
+}